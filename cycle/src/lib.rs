@@ -0,0 +1,298 @@
+use std::{collections::{HashMap, hash_map::DefaultHasher}, hash::{Hash, Hasher}};
+
+// Anything stepping deterministically from one state to the next
+pub trait Simulation
+{
+    // Two equal fingerprints must mean the same state (a hash is fine if collisions are unlikely enough)
+    type Fingerprint: Eq + Hash;
+
+    fn step(&mut self);
+    fn fingerprint(&self) -> Self::Fingerprint;
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Strategy
+{
+    // Two pointers at speed 1 and 2, constant memory
+    Floyd,
+    // Teleporting tortoise, constant memory and fewer steps than Floyd
+    Brent,
+    // Remember every fingerprint, fewest steps but memory grows with the cycle end
+    HashMap
+}
+
+// States from step `start` repeat every `length` steps
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Cycle
+{
+    pub start: usize,
+    pub length: usize
+}
+
+impl Cycle
+{
+    // Smallest step with the same state as step `n`
+    pub fn reduce(&self, n: usize) -> usize
+    {
+        if n < self.start { return n }
+
+        self.start + (n - self.start) % self.length
+    }
+}
+
+// Finds the cycle reached from `simulation`, which is left untouched (never returns if there is none)
+pub fn detect<S: Simulation + Clone>(simulation: &S, strategy: Strategy) -> Cycle
+{
+    match strategy
+    {
+        Strategy::Floyd => floyd(simulation),
+        Strategy::Brent => brent(simulation),
+        Strategy::HashMap => hash_map(simulation)
+    }
+}
+
+// Leaves `simulation` in the state it would have after `steps` steps, without running all of them
+pub fn fast_forward<S: Simulation + Clone>(simulation: &mut S, steps: usize, strategy: Strategy) -> Cycle
+{
+    let cycle = detect(simulation, strategy);
+
+    for _ in 0..cycle.reduce(steps)
+    {
+        simulation.step();
+    }
+
+    cycle
+}
+
+// Fingerprint for states that are expensive to keep around
+pub fn hash_of<T: Hash>(value: &T) -> u64
+{
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+fn stepped<S: Simulation + Clone>(simulation: &S, steps: usize) -> S
+{
+    let mut result = simulation.clone();
+
+    for _ in 0..steps
+    {
+        result.step();
+    }
+
+    result
+}
+
+// Both pointers at the same distance from the cycle start meet on it
+fn cycle_start<S: Simulation + Clone>(simulation: &S, length: usize) -> usize
+{
+    let mut tortoise = simulation.clone();
+    let mut hare = stepped(simulation, length);
+    let mut start = 0;
+
+    while tortoise.fingerprint() != hare.fingerprint()
+    {
+        tortoise.step();
+        hare.step();
+        start += 1;
+    }
+
+    start
+}
+
+fn floyd<S: Simulation + Clone>(simulation: &S) -> Cycle
+{
+    let mut tortoise = stepped(simulation, 1);
+    let mut hare = stepped(simulation, 2);
+
+    while tortoise.fingerprint() != hare.fingerprint()
+    {
+        tortoise.step();
+        hare.step();
+        hare.step();
+    }
+
+    // Tortoise is on the cycle, walk it once around
+    let fingerprint = tortoise.fingerprint();
+    let mut length = 1;
+
+    tortoise.step();
+
+    while tortoise.fingerprint() != fingerprint
+    {
+        tortoise.step();
+        length += 1;
+    }
+
+    Cycle { start: cycle_start(simulation, length), length }
+}
+
+fn brent<S: Simulation + Clone>(simulation: &S) -> Cycle
+{
+    let mut power = 1;
+    let mut length = 1;
+
+    let mut tortoise = simulation.fingerprint();
+    let mut hare = stepped(simulation, 1);
+
+    while tortoise != hare.fingerprint()
+    {
+        // Tortoise jumps to the hare every power of two
+        if power == length
+        {
+            tortoise = hare.fingerprint();
+            power *= 2;
+            length = 0;
+        }
+
+        hare.step();
+        length += 1;
+    }
+
+    Cycle { start: cycle_start(simulation, length), length }
+}
+
+fn hash_map<S: Simulation + Clone>(simulation: &S) -> Cycle
+{
+    let mut seen = HashMap::new();
+    let mut current = simulation.clone();
+    let mut step = 0;
+
+    loop
+    {
+        if let Some(first_seen) = seen.insert(current.fingerprint(), step)
+        {
+            return Cycle { start: first_seen, length: step - first_seen };
+        }
+
+        current.step();
+        step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{detect, fast_forward, hash_of, Cycle, Simulation, Strategy};
+
+    const STRATEGIES: [Strategy; 3] = [Strategy::Floyd, Strategy::Brent, Strategy::HashMap];
+
+    // x -> x² + 1 mod m, the classic rho shape
+    #[derive(Clone)]
+    struct Rho
+    {
+        x: u64,
+        modulus: u64
+    }
+
+    impl Simulation for Rho
+    {
+        type Fingerprint = u64;
+
+        fn step(&mut self)
+        {
+            self.x = (self.x * self.x + 1) % self.modulus;
+        }
+
+        fn fingerprint(&self) -> u64
+        {
+            self.x
+        }
+    }
+
+    #[derive(Clone)]
+    struct Counter
+    {
+        values: Vec<u8>
+    }
+
+    impl Simulation for Counter
+    {
+        type Fingerprint = u64;
+
+        fn step(&mut self)
+        {
+            self.values.iter_mut().for_each(|v| *v = (*v + 1) % 7);
+        }
+
+        fn fingerprint(&self) -> u64
+        {
+            hash_of(&self.values)
+        }
+    }
+
+    fn naive(simulation: &Rho) -> Cycle
+    {
+        let mut states = vec![simulation.x];
+        let mut current = simulation.clone();
+
+        loop
+        {
+            current.step();
+
+            if let Some(start) = states.iter().position(|x| *x == current.x)
+            {
+                return Cycle { start, length: states.len() - start };
+            }
+
+            states.push(current.x);
+        }
+    }
+
+    #[test]
+    fn test_strategies_agree()
+    {
+        for modulus in [1, 2, 10, 255, 1000, 1009]
+        {
+            for x in [0, 3, 17]
+            {
+                let rho = Rho { x: x % modulus, modulus };
+                let expected = naive(&rho);
+
+                for strategy in STRATEGIES
+                {
+                    assert_eq!(detect(&rho, strategy), expected, "{:?} {} {}", strategy, modulus, x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fast_forward()
+    {
+        let start = Rho { x: 3, modulus: 1000 };
+
+        for strategy in STRATEGIES
+        {
+            for steps in [0, 1, 5, 100, 12345]
+            {
+                let mut fast = start.clone();
+                let mut slow = start.clone();
+
+                fast_forward(&mut fast, steps, strategy);
+                (0..steps).for_each(|_| slow.step());
+
+                assert_eq!(fast.x, slow.x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pure_cycle()
+    {
+        let counter = Counter { values: vec![1, 2, 3] };
+
+        for strategy in STRATEGIES
+        {
+            assert_eq!(detect(&counter, strategy), Cycle { start: 0, length: 7 });
+        }
+
+        let mut counter = counter;
+        fast_forward(&mut counter, 1_000_000_000_000, Strategy::Brent);
+
+        // 10^12 = 1 mod 7
+        assert_eq!(counter.values, vec![2, 3, 4]);
+    }
+}
@@ -1,19 +1,38 @@
-use std::{collections::HashMap, ops::Range, io::{BufReader, BufRead}, fs::File};
+use std::{collections::{HashMap, HashSet, VecDeque}, ops::Range, io::BufRead};
 
 use crate::range;
 
 const ENTRY_MAP: &str = "seed";
+const EXIT_MAP: &str = "location";
 
 pub struct Manager
 {
     seeds: Vec<u64>,
     seed_ranges: Vec<Range<u64>>,
-    maps: HashMap<String, Map>
+    maps: Vec<Map>
 }
 
+#[derive(PartialEq, Eq, Debug)]
+pub enum PathError
+{
+    // No chain of maps between the categories
+    Unreachable,
+    // Several chains giving different values
+    Conflicting
+}
+
+#[derive(Debug)]
+pub enum ParseError
+{
+    // The input could not be read
+    Read(std::io::Error),
+    // A category can be reached from itself
+    Cycle
+}
+
+#[derive(PartialEq, Eq, Debug)]
 pub struct Map
 {
-    #[allow(dead_code)]
     src: String,
     dst: String,
 
@@ -22,21 +41,15 @@ pub struct Map
 
 impl Manager
 {
-    pub fn from_input(buffer: BufReader<File>) -> Self
+    pub fn from_input<R: BufRead>(buffer: R) -> Result<Self, ParseError>
     {
-        // First line is see
-        let lines = buffer.lines();
-
         let mut manager = Manager {
             seeds: vec![],
             seed_ranges: vec![],
-            maps: HashMap::new()
+            maps: vec![]
         };
 
-        // Initialize with an unused entry
-        let mut current_map = String::from("");
-
-        for line in lines
+        for line in buffer.lines()
         {
             match line
             {
@@ -53,12 +66,12 @@ impl Manager
 
                         // Part 1
                         manager.seeds = seeds_content
-                            .split(' ')
+                            .split_whitespace()
                             .map(|n| n.parse::<u64>().unwrap())
                             .collect();
 
                         // Part 2
-                        let mut seed_iter = seeds_content.split(' ');
+                        let mut seed_iter = seeds_content.split_whitespace();
                         while let Some(seed) = seed_iter.next()
                         {
                             let seed_range_start = seed.parse::<u64>().unwrap();
@@ -75,104 +88,212 @@ impl Manager
                         let map_src = map_parts.next().unwrap();
                         let map_dst = map_parts.next().unwrap();
 
-                        current_map = map_src.to_string();
-
-                        manager.maps.insert(map_src.to_string(), Map {
-                            src: map_src.to_string(),
-                            dst: map_dst.to_string(),
-
-                            ranges: vec![]
-                        });
+                        manager.maps.push(Map::identity(map_src, map_dst));
                     }
                     // Map line
                     else
                     {
                         let mut range_parts = content
-                            .split(' ')
+                            .split_whitespace()
                             .map(|n| n.parse::<u64>().unwrap());
 
                         let dst_range_start = range_parts.next().unwrap();
                         let src_range_start = range_parts.next().unwrap();
                         let size            = range_parts.next().unwrap();
 
-                        manager.maps.entry(current_map.clone())
-                            .and_modify(|e| {
-                                e.ranges.push((
-                                    src_range_start..(src_range_start + size),
-                                    dst_range_start..(dst_range_start + size)
-                                ));
-                            });
+                        match manager.maps.last_mut()
+                        {
+                            Some(map) => map.ranges.push((
+                                src_range_start..(src_range_start + size),
+                                dst_range_start..(dst_range_start + size)
+                            )),
+                            None => panic!("Range found before any map header: {0}", content)
+                        }
                     }
                 },
-                Err(e) => println!("Error while reading file: {:?}", e)
+                Err(e) => return Err(ParseError::Read(e))
             }
         }
 
-        return manager;
+        if manager.categories_order().is_none()
+        {
+            return Err(ParseError::Cycle);
+        }
+
+        return Ok(manager);
     }
 
     pub fn lowest_location(&self) -> u64
     {
-        let mut lowest_location = u64::MAX;
+        let almanac = self.collapse();
+
+        return self.seeds
+            .iter()
+            .map(|seed| almanac.value_for(*seed))
+            .min()
+            .unwrap_or(u64::MAX);
+    }
+
+    pub fn lowest_ranges_location(&self) -> u64
+    {
+        let almanac = self.collapse();
+
+        return self.seed_ranges
+            .iter()
+            .flat_map(|range| almanac.translate(range.clone()))
+            .map(|r| r.start)
+            .min()
+            .unwrap_or(u64::MAX);
+    }
+
+    pub fn seeds_for_location(&self, location: u64) -> Vec<u64>
+    {
+        return self.collapse().inverse_values(location);
+    }
 
-        for seed in &self.seeds
+    // Single seed -> location map
+    pub fn collapse(&self) -> Map
+    {
+        match self.collapse_between(ENTRY_MAP, EXIT_MAP)
         {
-            lowest_location = std::cmp::min(lowest_location, self.get_mapped_value(*seed, "location", ENTRY_MAP));
+            Ok(map) => map,
+            Err(error) => panic!("Cannot go from {0} to {1}: {2:?}", ENTRY_MAP, EXIT_MAP, error)
         }
+    }
 
-        return lowest_location
+    // Every path between two categories must give the same value
+    pub fn value_between(&self, value: u64, src: &str, dst: &str) -> Result<u64, PathError>
+    {
+        return self.reach(src, dst, value, |value, map| map.value_for(*value));
     }
 
-    pub fn lowest_ranges_location(&self) -> u64
+    // Compose the maps along every path between two categories, they must agree for every value
+    pub fn collapse_between(&self, src: &str, dst: &str) -> Result<Map, PathError>
     {
-        let mut lowest_location = u64::MAX;
+        // Composed maps are normalized, same ranges means same values
+        return self.reach(src, dst, Map::identity(src, src), |collapsed, map| collapsed.compose(map));
+    }
 
-        for seed_range in &self.seed_ranges
+    // Goes through the categories in topological order, keeping the distinct results of the paths reaching each one,
+    // so every map is applied once per distinct result instead of once per path
+    fn reach<T: PartialEq>(&self, src: &str, dst: &str, start: T, apply: impl Fn(&T, &Map) -> T) -> Result<T, PathError>
+    {
+        // Cycles are rejected when parsing
+        let order = self.categories_order().unwrap();
+        let mut reached: HashMap<&str, Vec<T>> = HashMap::from([(src, vec![start])]);
+
+        for category in order.iter().skip_while(|c| *c != src)
         {
-            lowest_location = lowest_location.min(self.lowest_range_location(seed_range))
+            if category == dst { break }
+
+            // Every map going to this category comes from an earlier one, nothing else can reach it
+            let results = match reached.remove(category.as_str())
+            {
+                Some(results) => results,
+                None => continue
+            };
+
+            for map in self.maps.iter().filter(|m| m.src == *category)
+            {
+                let next = reached.entry(map.dst.as_str()).or_default();
+
+                for result in &results
+                {
+                    let mapped = apply(result, map);
+                    if !next.contains(&mapped) { next.push(mapped) }
+                }
+            }
         }
 
-        return lowest_location;
+        let mut results = reached.remove(dst).unwrap_or_default();
+
+        match results.len()
+        {
+            0 => Err(PathError::Unreachable),
+            1 => Ok(results.pop().unwrap()),
+            _ => Err(PathError::Conflicting)
+        }
     }
 
-    pub fn lowest_range_location(&self, range: &Range::<u64>) -> u64
+    // Categories sorted so that every map goes forward, None if there is a cycle
+    pub fn categories_order(&self) -> Option<Vec<String>>
     {
-        let mut translated_ranges = vec![range.clone()];
-        let mut current_map = String::from(ENTRY_MAP);
+        let mut categories: HashSet<&str> = HashSet::new();
+        let mut incoming: HashMap<&str, usize> = HashMap::new();
 
-        while let Some(map) = self.maps.get(&current_map)
+        for map in &self.maps
         {
-            current_map = map.dst.clone();
-
-            translated_ranges = translated_ranges
-                .into_iter()
-                .flat_map(|range| map.translate(range))
-                .collect();
+            categories.insert(map.src.as_str());
+            categories.insert(map.dst.as_str());
+            *incoming.entry(map.dst.as_str()).or_default() += 1;
         }
 
-        return translated_ranges
+        let mut roots: Vec<&str> = categories
             .iter()
-            .map(|r| r.start)
-            .min()
-            .unwrap_or(u64::MAX);
+            .filter(|c| !incoming.contains_key(*c))
+            .copied()
+            .collect();
+
+        roots.sort();
+
+        let mut queue = VecDeque::from(roots);
+        let mut order = vec![];
+
+        while let Some(category) = queue.pop_front()
+        {
+            order.push(category.to_string());
+
+            for map in self.maps.iter().filter(|m| m.src == category)
+            {
+                let count = incoming.get_mut(map.dst.as_str()).unwrap();
+                *count -= 1;
+
+                if *count == 0 { queue.push_back(map.dst.as_str()) }
+            }
+        }
+
+        if order.len() != categories.len() { return None }
+
+        return Some(order);
     }
+}
 
-    fn get_mapped_value(&self, value: u64, dst_map: &str, src_map: &str) -> u64
+impl From<&str> for Manager
+{
+    fn from(value: &str) -> Self
     {
-        if dst_map == src_map { return value }
-
-        let mapped_value  = match self.maps.get(src_map)
+        match Manager::from_input(value.as_bytes())
         {
-            Some(map) => (map.dst.as_str(), map.value_for(value)),
-            None => panic!("Unknown map {0}", src_map)
-        };
+            Ok(manager) => manager,
+            Err(error) => panic!("Invalid almanac: {0}", error)
+        }
+    }
+}
 
-        return self.get_mapped_value(mapped_value.1, dst_map, mapped_value.0)
+impl std::fmt::Display for ParseError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            ParseError::Read(error) => write!(f, "cannot read the input ({0})", error),
+            ParseError::Cycle => write!(f, "categories contain a cycle")
+        }
     }
 }
 
 impl Map
 {
+    pub fn identity(src: &str, dst: &str) -> Self
+    {
+        return Map {
+            src: src.to_string(),
+            dst: dst.to_string(),
+
+            ranges: vec![]
+        };
+    }
+
     pub fn value_for(&self, src_value: u64) -> u64
     {
         for range_tuple in &self.ranges
@@ -189,51 +310,330 @@ impl Map
 
     pub fn translate(&self, initial_range: Range<u64>) -> Vec<Range<u64>>
     {
-        let mut translated = vec![initial_range.clone()];
+        return self.split(initial_range)
+            .into_iter()
+            .map(|piece| piece.1)
+            .collect();
+    }
+
+    // Cut a range into consecutive pieces, each one being linear, with its translated range
+    pub fn split(&self, initial_range: Range<u64>) -> Vec<(Range<u64>, Range<u64>)>
+    {
+        let mut mapped_ranges: Vec<&(Range<u64>, Range<u64>)> = self.ranges
+            .iter()
+            .filter(|r| range::has_intersect(&initial_range, &r.0))
+            .collect();
+
+        mapped_ranges.sort_by_key(|r| r.0.start);
+
         let mut output = vec![];
+        let mut cursor = initial_range.start;
 
-        while let Some(range) = translated.pop()
+        for mapped_range in mapped_ranges
         {
-            // If we don't find intersect -> non-mapped ranges
-            let mut found_intersect = false;
+            let intersect = range::intersect(&initial_range, &mapped_range.0);
+            let start = intersect.start.max(cursor);
 
-            for mapped_range in &self.ranges
+            // Overlapping source ranges, the first one wins (same as value_for)
+            if start >= intersect.end { continue }
+
+            // Hole before the mapped range, values are kept as is
+            if cursor < start
             {
-                // If we have no intesection, skip
-                if !range::has_intersect(&range, &mapped_range.0) { continue }
+                output.push((cursor..start, cursor..start));
+            }
+
+            let shift = mapped_range.1.start as i128 - mapped_range.0.start as i128;
+            output.push((start..intersect.end, shift_range(&(start..intersect.end), shift)));
 
-                found_intersect = true;
+            cursor = intersect.end;
+        }
 
-                // We have an intersection, so compute interest range
-                let intersect = range::intersect(&range, &mapped_range.0);
+        if cursor < initial_range.end
+        {
+            output.push((cursor..initial_range.end, cursor..initial_range.end));
+        }
 
-                // intersect_start - range.start = shift
-                let translated_range = (intersect.start - mapped_range.0.start + mapped_range.1.start)..(intersect.end - mapped_range.0.start + mapped_range.1.start);
+        return output
+    }
 
-                // We add intersection to output
-                output.push(translated_range);
+    // All the linear pieces of the map, covering the whole domain but u64::MAX:
+    // ranges are half open so they can't contain it, it is always kept as is
+    pub fn pieces(&self) -> Vec<(Range<u64>, Range<u64>)>
+    {
+        return self.split(0..u64::MAX);
+    }
 
-                // We also need to re-add remaining intersect (before/after) to find non-mapped values (holes)
-                if intersect.start > range.start
-                {
-                    translated.push(range.start..intersect.start);
-                }
+    // Build the map equivalent to applying self, then next
+    pub fn compose(&self, next: &Map) -> Map
+    {
+        if self.dst != next.src
+        {
+            panic!("Cannot compose {0}-to-{1} with {2}-to-{3}", self.src, self.dst, next.src, next.dst);
+        }
+
+        let mut ranges: Vec<(Range<u64>, Range<u64>)> = vec![];
+
+        for (src_range, dst_range) in self.pieces()
+        {
+            for (next_src_range, next_dst_range) in next.split(dst_range.clone())
+            {
+                let start = src_range.start + (next_src_range.start - dst_range.start);
+                let end   = start + (next_src_range.end - next_src_range.start);
 
-                if intersect.end < range.end
+                // Identity pieces are implicit
+                if start == next_dst_range.start { continue }
+
+                // Merge with previous piece if it is contiguous with the same shift
+                if let Some(last) = ranges.last_mut()
                 {
-                    translated.push(intersect.end..range.end);
+                    if last.0.end == start && last.1.end == next_dst_range.start
+                    {
+                        last.0.end = end;
+                        last.1.end = next_dst_range.end;
+                        continue;
+                    }
                 }
 
-                // We break to pop remaining ranges
-                break;
+                ranges.push((start..end, next_dst_range));
             }
+        }
 
-            if !found_intersect
-            {
-                output.push(range);
-            }
+        return Map {
+            src: self.src.clone(),
+            dst: next.dst.clone(),
+
+            ranges
+        };
+    }
+
+    // All the source ranges whose values are mapped inside the output range
+    pub fn preimage(&self, output_range: Range<u64>) -> Vec<Range<u64>>
+    {
+        let mut preimage: Vec<Range<u64>> = self.pieces()
+            .into_iter()
+            .filter(|piece| range::has_intersect(&piece.1, &output_range))
+            .map(|piece| {
+                let intersect = range::intersect(&piece.1, &output_range);
+                let shift = piece.0.start as i128 - piece.1.start as i128;
+
+                shift_range(&intersect, shift)
+            })
+            .collect();
+
+        preimage.sort_by_key(|r| r.start);
+
+        return range::merge(preimage);
+    }
+
+    pub fn inverse_values(&self, dst_value: u64) -> Vec<u64>
+    {
+        // Not in any piece, only u64::MAX goes to u64::MAX
+        if dst_value == u64::MAX { return vec![u64::MAX] }
+
+        return self.preimage(dst_value..(dst_value + 1))
+            .into_iter()
+            .flatten()
+            .collect();
+    }
+}
+
+fn shift_range(range: &Range<u64>, shift: i128) -> Range<u64>
+{
+    let start = (range.start as i128 + shift) as u64;
+    let end   = (range.end as i128 + shift) as u64;
+
+    return start..end;
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Map, Manager, ParseError, PathError};
+
+    const EXAMPLE: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    fn map(src: &str, dst: &str, ranges: Vec<(u64, u64, u64)>) -> Map
+    {
+        let mut map = Map::identity(src, dst);
+
+        map.ranges = ranges
+            .into_iter()
+            .map(|(dst_start, src_start, size)| (src_start..(src_start + size), dst_start..(dst_start + size)))
+            .collect();
+
+        return map;
+    }
+
+    #[test]
+    fn test_compose()
+    {
+        let seed_to_soil = map("seed", "soil", vec![(50, 98, 2), (52, 50, 48)]);
+        let soil_to_fertilizer = map("soil", "fertilizer", vec![(0, 15, 37), (37, 52, 2), (39, 0, 15)]);
+
+        let composed = seed_to_soil.compose(&soil_to_fertilizer);
+
+        for seed in 0..200
+        {
+            assert_eq!(composed.value_for(seed), soil_to_fertilizer.value_for(seed_to_soil.value_for(seed)));
         }
+    }
 
-        return output
+    #[test]
+    fn test_preimage()
+    {
+        let seed_to_soil = map("seed", "soil", vec![(50, 98, 2), (52, 50, 48)]);
+
+        assert_eq!(seed_to_soil.inverse_values(81), vec![79]);
+        assert_eq!(seed_to_soil.inverse_values(50), vec![98]);
+        assert_eq!(seed_to_soil.preimage(48..53), vec![48..51, 98..100]);
+
+        // 10 and 20 both map to 20
+        let not_bijective = map("seed", "soil", vec![(20, 10, 1)]);
+
+        assert_eq!(not_bijective.inverse_values(20), vec![10, 20]);
+
+        // Last value of the domain
+        let to_end = map("seed", "soil", vec![(u64::MAX - 5, 0, 5)]);
+
+        assert_eq!(to_end.inverse_values(u64::MAX - 1), vec![4, u64::MAX - 1]);
+        assert_eq!(to_end.inverse_values(u64::MAX), vec![u64::MAX]);
+        assert_eq!(to_end.value_for(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_example()
+    {
+        let manager = Manager::from(EXAMPLE);
+
+        assert_eq!(manager.lowest_location(), 35);
+        assert_eq!(manager.lowest_ranges_location(), 46);
+        assert_eq!(manager.seeds_for_location(46), vec![82]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_value_between()
+    {
+        let manager = Manager::from(EXAMPLE);
+
+        assert_eq!(manager.value_between(79, "seed", "soil"), Ok(81));
+        assert_eq!(manager.value_between(53, "soil", "water"), Ok(27));
+        assert_eq!(manager.value_between(14, "seed", "location"), Ok(43));
+        assert_eq!(manager.value_between(14, "location", "seed"), Err(PathError::Unreachable));
+    }
+
+    #[test]
+    fn test_branching_categories()
+    {
+        let manager = Manager::from("seeds: 1 2
+
+seed-to-soil map:
+10 0 5
+
+seed-to-water map:
+20 0 5
+
+soil-to-location map:
+0 10 5");
+
+        assert_eq!(manager.value_between(3, "seed", "water"), Ok(23));
+        assert_eq!(manager.value_between(3, "seed", "location"), Ok(3));
+        assert_eq!(manager.categories_order().unwrap()[0], "seed");
+    }
+
+    #[test]
+    fn test_conflicting_paths()
+    {
+        // seed -> soil -> location and seed -> water -> location
+        let content = "seeds: 1 2
+
+seed-to-soil map:
+10 0 5
+
+seed-to-water map:
+20 0 5
+
+soil-to-location map:
+30 10 5
+
+water-to-location map:
+";
+        let agreeing = Manager::from(format!("{}30 20 5", content).as_str());
+
+        assert_eq!(agreeing.value_between(3, "seed", "location"), Ok(33));
+        // 10 goes to 30 through soil but stays 10 through water
+        assert_eq!(agreeing.value_between(10, "seed", "location"), Err(PathError::Conflicting));
+        assert!(agreeing.collapse_between("seed", "location").is_err());
+        assert!(agreeing.collapse_between("seed", "water").is_ok());
+
+        let conflicting = Manager::from(format!("{}40 20 5", content).as_str());
+
+        assert_eq!(conflicting.value_between(3, "seed", "location"), Err(PathError::Conflicting));
+        assert_eq!(conflicting.value_between(3, "seed", "soil"), Ok(13));
+    }
+
+    #[test]
+    fn test_many_paths()
+    {
+        // 40 diamonds in a row, 2^40 paths that all agree
+        let mut content = String::from("seeds: 1 2\n");
+
+        for i in 0..40
+        {
+            content += &format!("\nc{0}-to-left{0} map:\n\nc{0}-to-right{0} map:\n", i);
+            content += &format!("\nleft{0}-to-c{1} map:\n1 0 1\n\nright{0}-to-c{1} map:\n1 0 1\n", i, i + 1);
+        }
+
+        let manager = Manager::from(content.as_str());
+
+        assert_eq!(manager.value_between(0, "c0", "c40"), Ok(1));
+        assert_eq!(manager.value_between(5, "c0", "c40"), Ok(5));
+        assert!(manager.collapse_between("c0", "c40").is_ok());
+    }
+
+    #[test]
+    fn test_cycle()
+    {
+        let content = "seeds: 1 2
+
+seed-to-soil map:
+10 0 5
+
+soil-to-seed map:
+0 10 5";
+
+        assert!(matches!(Manager::from_input(content.as_bytes()), Err(ParseError::Cycle)));
+    }
+}
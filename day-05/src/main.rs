@@ -8,8 +8,25 @@ fn main()
     let file = File::open("./day-05/input.txt").unwrap();
     let buffer = BufReader::new(file);
 
-    let manager = farm::Manager::from_input(buffer);
+    let manager = match farm::Manager::from_input(buffer)
+    {
+        Ok(manager) => manager,
+        Err(error) => panic!("Invalid almanac: {0}", error)
+    };
+
+    let lowest_location = manager.lowest_ranges_location();
 
     println!("Result: {0}", manager.lowest_location());
-    println!("Result 2: {0}", manager.lowest_ranges_location());
+    println!("Result 2: {0}", lowest_location);
+    let seeds = manager.seeds_for_location(lowest_location);
+    println!("Seeds for location {0}: {1:?}", lowest_location, seeds);
+
+    // Value of the first of them in every category on the way
+    if let Some(seed) = seeds.first()
+    {
+        for category in manager.categories_order().unwrap()
+        {
+            println!("  {0}: {1:?}", category, manager.value_between(*seed, "seed", &category));
+        }
+    }
 }
\ No newline at end of file
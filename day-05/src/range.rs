@@ -21,4 +21,22 @@ pub fn intersect<T>(a: &Range<T>, b: &Range<T>) -> Range<T>
     let intersect_end   = a.end.min(b.end);
 
     return intersect_start..intersect_end;
-}
\ No newline at end of file
+}
+
+// Merge overlapping or contiguous ranges, input must be sorted by start
+pub fn merge<T>(ranges: Vec<Range<T>>) -> Vec<Range<T>>
+    where T: Ord + Copy
+{
+    let mut merged: Vec<Range<T>> = vec![];
+
+    for range in ranges
+    {
+        match merged.last_mut()
+        {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range)
+        }
+    }
+
+    return merged;
+}
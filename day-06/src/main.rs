@@ -1,4 +1,4 @@
-use std::{fs::File, io::{BufReader, BufRead}};
+use std::{fs::File, io::{BufReader, BufRead}, ops::RangeInclusive};
 
 // (time, distance)
 pub struct Race(u64, u64);
@@ -51,9 +51,97 @@ fn parse_races(buffer: BufReader<File>) -> (Vec<Race>, Race)
 
 fn possible_solutions(race: &Race) -> u64
 {
-    let delta = (race.0.pow(2) - 4 * race.1) as f64;
-    let x1 = ((-(race.0 as f64) - delta.sqrt()) / (-2f64)).ceil() as u64;
-    let x2 = ((-(race.0 as f64) + delta.sqrt()) / (-2f64)).floor() as u64;
+    return match winning_holds(race)
+    {
+        Some(holds) => holds.end() - holds.start() + 1,
+        None => 0
+    };
+}
+
+// Hold times h where h * (time - h) > distance, ie. between the roots of h² - time * h + distance
+fn winning_holds(race: &Race) -> Option<RangeInclusive<u64>>
+{
+    let time = race.0 as u128;
+    let distance = race.1 as u128;
+
+    let beats = |hold: u128| hold * (time - hold) > distance;
+
+    // No real roots (or a single one, which only ties the record)
+    if time * time <= 4 * distance { return None }
 
-    return x1 - x2 - 1;
-}
\ No newline at end of file
+    let delta_sqrt = isqrt(time * time - 4 * distance);
+
+    // Integer sqrt is floored, so the candidate can be one step off in either direction
+    let mut low = (time - delta_sqrt) / 2;
+
+    while low > 0 && beats(low - 1) { low -= 1 }
+    while low <= time / 2 && !beats(low) { low += 1 }
+
+    if low > time / 2 { return None }
+
+    // Distance is symmetric around time / 2
+    return Some((low as u64)..=((time - low) as u64));
+}
+
+fn isqrt(n: u128) -> u128
+{
+    if n < 2 { return n }
+
+    // Newton iterations starting above the root always decrease towards floor(sqrt(n))
+    let mut x = 1u128 << ((128 - n.leading_zeros()) / 2 + 1);
+
+    loop
+    {
+        let next = (x + n / x) / 2;
+        if next >= x { return x }
+
+        x = next;
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{Race, winning_holds, possible_solutions, isqrt};
+
+    #[test]
+    fn test_example()
+    {
+        assert_eq!(winning_holds(&Race(7, 9)), Some(2..=5));
+        assert_eq!(winning_holds(&Race(15, 40)), Some(4..=11));
+        assert_eq!(winning_holds(&Race(30, 200)), Some(11..=19));
+        assert_eq!(possible_solutions(&Race(71530, 940200)), 71503);
+    }
+
+    #[test]
+    fn test_degenerate()
+    {
+        // Negative discriminant
+        assert_eq!(winning_holds(&Race(3, 10)), None);
+        // Zero discriminant, best hold only ties the record
+        assert_eq!(winning_holds(&Race(4, 4)), None);
+        assert_eq!(winning_holds(&Race(0, 0)), None);
+        assert_eq!(winning_holds(&Race(2, 0)), Some(1..=1));
+        assert_eq!(possible_solutions(&Race(1, 0)), 0);
+    }
+
+    #[test]
+    fn test_large_time()
+    {
+        let holds = winning_holds(&Race(u64::MAX, 1)).unwrap();
+
+        assert_eq!(holds, 1..=(u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_isqrt()
+    {
+        for n in 0..10_000u128
+        {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n);
+        }
+
+        assert_eq!(isqrt(u64::MAX as u128 * u64::MAX as u128), u64::MAX as u128);
+    }
+}
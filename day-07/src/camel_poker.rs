@@ -1,11 +1,9 @@
-use std::{cmp::Ordering, collections::HashMap, fmt::{Formatter, Error}};
+use std::{cmp::Ordering, collections::HashMap, fmt::{Formatter, Error}, rc::Rc};
 
-#[allow(dead_code)]
 const CARDS_ORDER_1: [char; 13] = [
     'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2'
 ];
 
-#[allow(dead_code)]
 const CARDS_ORDER_2: [char; 13] = [
     'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J'
 ];
@@ -18,54 +16,167 @@ pub struct Bid
 
 pub struct Hand
 {
-    pub cards: [Card; 5]
+    pub cards: [Card; 5],
+    pub rules: Rc<RuleSet>
 }
 
 #[derive(Debug)]
 pub struct Card(pub char);
 
-impl Hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category
 {
-    pub fn as_string(&self) -> String
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak
+{
+    // Compare cards one by one, in the order they were dealt
+    DealtOrder,
+    // Compare cards from the strongest to the weakest
+    Strongest
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleSet
+{
+    // Strongest card first
+    pub cards_order: Vec<char>,
+    // Cards that join the biggest group to make the best possible category
+    pub wildcards: Vec<char>,
+    pub tie_break: TieBreak
+}
+
+impl RuleSet
+{
+    pub fn standard() -> Self
     {
-        self.cards.iter().map(|c| c.0).collect::<String>()
+        RuleSet {
+            cards_order: CARDS_ORDER_1.to_vec(),
+            wildcards: vec![],
+            tie_break: TieBreak::DealtOrder
+        }
+    }
+
+    pub fn jokers() -> Self
+    {
+        RuleSet {
+            cards_order: CARDS_ORDER_2.to_vec(),
+            wildcards: vec!['J'],
+            tie_break: TieBreak::DealtOrder
+        }
     }
 
-    pub fn value(&self) -> u32
+    pub fn category(&self, cards: &[Card]) -> Category
     {
         // Group cards by value
-        let mut grouped_cards = self.cards
+        let grouped_cards = cards
             .iter()
+            .filter(|c| !self.wildcards.contains(&c.0))
             .fold(HashMap::new(), |mut acc, card| {
                 *acc.entry(card.0).or_insert(0) += 1;
 
                 acc
             });
 
-        let n_joker = grouped_cards.remove(&'J').unwrap_or(0);
+        let n_wildcards = cards.len() - grouped_cards.values().sum::<usize>();
+
+        // We don't really care about which card form the group
+        let mut groups = grouped_cards.into_values().collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        // Wildcards always join the biggest group (or form one if we only have wildcards)
+        match groups.first_mut()
+        {
+            Some(max_group) => *max_group += n_wildcards,
+            None => groups.push(n_wildcards)
+        }
+
+        let second_max = groups.get(1).copied().unwrap_or(0);
+
+        return match (groups[0], second_max)
+        {
+            (5.., _) => Category::FiveOfAKind,
+            (4, _)   => Category::FourOfAKind,
+            (3, 2)   => Category::FullHouse,
+            (3, _)   => Category::ThreeOfAKind,
+            (2, 2)   => Category::TwoPair,
+            (2, _)   => Category::OnePair,
+            _        => Category::HighCard
+        }
+    }
+
+    pub fn compare_cards(&self, a: &Card, b: &Card) -> Ordering
+    {
+        let a_index = self.strength(a);
+        let b_index = self.strength(b);
+
+        // Lower index means stronger card
+        return b_index.cmp(&a_index);
+    }
+
+    pub fn compare_hands(&self, a: &[Card], b: &[Card]) -> Ordering
+    {
+        let category_order = self.category(a).cmp(&self.category(b));
+        if category_order != Ordering::Equal { return category_order }
+
+        let (mut a, mut b) = (a.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
 
-        // If we only have joker, we have a five of a kind
-        if n_joker == 5 { return 6 }
+        if self.tie_break == TieBreak::Strongest
+        {
+            a.sort_by(|x, y| self.compare_cards(y, x));
+            b.sort_by(|x, y| self.compare_cards(y, x));
+        }
 
-        // We don't really care about with card form the group
-        let max_group = grouped_cards.iter().max_by(|a, b| a.1.cmp(b.1)).unwrap();
-        let second_max = grouped_cards.iter().filter(|(k, _)| **k != *max_group.0).max_by(|a, b| a.1.cmp(b.1));
+        return a.iter().zip(&b)
+            .map(|(x, y)| self.compare_cards(x, y))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal);
+    }
 
-        let max_group_with_joker = max_group.1 + n_joker;
+    pub fn strength(&self, card: &Card) -> usize
+    {
+        match self.cards_order.iter().position(|c| *c == card.0)
+        {
+            Some(index) => index,
+            None => panic!("Unknown card {0}", card.0)
+        }
+    }
+}
 
-        return match max_group_with_joker
+impl Hand
+{
+    pub fn with_rules(value: &str, rules: Rc<RuleSet>) -> Self
+    {
+        if value.chars().count() != 5
         {
-            4..=5 => max_group_with_joker + 1,
-            3 => {
-                if second_max.is_some() && *second_max.unwrap().1 == 2 { 4 }
-                else { max_group_with_joker }
-            },
-            2 => {
-                if second_max.is_some() && *second_max.unwrap().1 == 2 { 2 }
-                else { 1 }
-            }
-            _ => 0
+            panic!("A hand must be 5 cards");
         }
+
+        let cards = value.chars()
+            .map(Card)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+
+        Hand { cards, rules }
+    }
+
+    pub fn as_string(&self) -> String
+    {
+        self.cards.iter().map(|c| c.0).collect::<String>()
+    }
+
+    pub fn category(&self) -> Category
+    {
+        self.rules.category(&self.cards)
     }
 }
 
@@ -79,46 +190,44 @@ impl std::fmt::Debug for Hand
     }
 }
 
-impl From<&str> for Bid
+impl Bid
 {
-    fn from(value: &str) -> Self
+    pub fn with_rules(value: &str, rules: Rc<RuleSet>) -> Self
     {
         let mut parts = value.split(' ');
 
-        let hand = Hand::from(parts.next().unwrap());
+        let hand = Hand::with_rules(parts.next().unwrap(), rules);
         let bid = parts.next().unwrap().parse::<u32>().unwrap();
 
         Bid {
             amount: bid,
-            hand: hand
+            hand
         }
     }
 }
 
-impl From<&str> for Hand
+impl From<&str> for Bid
 {
     fn from(value: &str) -> Self
     {
-        if value.len() != 5
-        {
-            panic!("A hand must be 5 cards");
-        }
-
-        let cards = value.chars()
-            .map(|c| Card(c))
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+        Bid::with_rules(value, Rc::new(RuleSet::jokers()))
+    }
+}
 
-        Hand { cards: cards }
+impl From<&str> for Hand
+{
+    fn from(value: &str) -> Self
+    {
+        Hand::with_rules(value, Rc::new(RuleSet::jokers()))
     }
 }
 
+// Same as the ordering so sorting stays consistent: with some rules, different cards can tie
 impl PartialEq for Hand
 {
     fn eq(&self, other: &Self) -> bool
     {
-        self.cards == other.cards
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -136,27 +245,7 @@ impl Ord for Hand
 {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering
     {
-        let self_value  = self.value();
-        let other_value = other.value();
-
-        return if self_value == other_value
-        {
-            let first_match = self.cards.iter().zip(&other.cards)
-                .find(|(a, b)| a != b);
-
-            if let Some((a, b)) = first_match
-            {
-                a.cmp(b)
-            }
-            else
-            {
-                Ordering::Equal
-            }
-        }
-        else
-        {
-            self_value.cmp(&other_value)
-        };
+        self.rules.compare_hands(&self.cards, &other.cards)
     }
 }
 
@@ -170,56 +259,43 @@ impl PartialEq for Card
 
 impl Eq for Card {}
 
-impl PartialOrd for Card
-{
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
-    {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for Card
-{
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering
-    {
-        if self == other { return Ordering::Equal }
-
-
-        // Switch to 1 for part 1
-        let self_index  = CARDS_ORDER_2.into_iter().position(|c| c == self.0).unwrap();
-        let other_index = CARDS_ORDER_2.into_iter().position(|c| c == other.0).unwrap();
-
-        return if self_index < other_index { Ordering::Greater }
-               else { Ordering::Less };
-    }
-}
-
 #[cfg(test)]
 mod tests
 {
     mod CardTest
     {
-        use crate::camel_poker::Card;
+        use std::cmp::Ordering;
+
+        use crate::camel_poker::{Card, RuleSet};
 
         #[test]
         fn compare()
         {
+            let rules = RuleSet::jokers();
+
             let a = Card('2');
             let b = Card('7');
             let c = Card('A');
+            let j = Card('J');
 
-            assert!(a < b);
-            assert!(b < c);
-            assert!(a < c);
-            assert!(a <= a);
+            assert_eq!(rules.compare_cards(&a, &b), Ordering::Less);
+            assert_eq!(rules.compare_cards(&b, &c), Ordering::Less);
+            assert_eq!(rules.compare_cards(&a, &c), Ordering::Less);
+            assert_eq!(rules.compare_cards(&a, &a), Ordering::Equal);
             assert!(a == a);
             assert!(a != b);
+
+            // Card order comes from the rules
+            assert_eq!(rules.compare_cards(&j, &a), Ordering::Less);
+            assert_eq!(RuleSet::standard().compare_cards(&j, &a), Ordering::Greater);
         }
     }
 
     mod HandTest
     {
-        use crate::camel_poker::{Hand, Card};
+        use std::rc::Rc;
+
+        use crate::camel_poker::{Hand, Card, RuleSet};
 
         #[test]
         fn from_string()
@@ -234,7 +310,7 @@ mod tests
                 Card('6'),
                 Card('K'),
                 Card('8')
-            ]});
+            ], rules: Rc::new(RuleSet::jokers()) });
         }
 
         #[test]
@@ -263,4 +339,58 @@ mod tests
             assert!(pair < pair2);
         }
     }
-}
\ No newline at end of file
+
+    mod rule_set_test
+    {
+        use std::rc::Rc;
+
+        use crate::camel_poker::{Hand, Category, RuleSet, TieBreak};
+
+        #[test]
+        fn standard_rules()
+        {
+            let rules = Rc::new(RuleSet::standard());
+
+            let jacks = Hand::with_rules("JJJ23", rules.clone());
+            let pair = Hand::with_rules("KK677", rules.clone());
+
+            assert_eq!(jacks.category(), Category::ThreeOfAKind);
+            assert_eq!(pair.category(), Category::TwoPair);
+            assert!(pair < jacks);
+            assert!(Hand::with_rules("J2345", rules.clone()) > Hand::with_rules("T2345", rules));
+        }
+
+        #[test]
+        fn multiple_wildcards()
+        {
+            let rules = Rc::new(RuleSet {
+                wildcards: vec!['J', '2'],
+                ..RuleSet::jokers()
+            });
+
+            assert_eq!(Hand::with_rules("J2J2J", rules.clone()).category(), Category::FiveOfAKind);
+            assert_eq!(Hand::with_rules("J2345", rules.clone()).category(), Category::ThreeOfAKind);
+            assert_eq!(Hand::with_rules("KK2QQ", rules).category(), Category::FullHouse);
+        }
+
+        #[test]
+        fn strongest_tie_break()
+        {
+            let rules = Rc::new(RuleSet {
+                tie_break: TieBreak::Strongest,
+                ..RuleSet::standard()
+            });
+
+            let a = Hand::with_rules("23A45", rules.clone());
+            let b = Hand::with_rules("KQ234", rules.clone());
+
+            assert!(a > b);
+
+            // Same cards in another order tie, and are equal
+            let c = Hand::with_rules("A2345", rules);
+
+            assert_eq!(a.cmp(&c), std::cmp::Ordering::Equal);
+            assert!(a == c);
+        }
+    }
+}
@@ -1,37 +1,117 @@
-use std::{fs::File, io::{BufReader, BufRead}};
+use std::{fs::File, io::{BufReader, BufRead}, rc::Rc};
 
 mod camel_poker;
+// Standard poker evaluator built on the same cards, not needed by the puzzle itself
+mod poker;
 
-use camel_poker::Bid;
+use camel_poker::{Bid, RuleSet};
+use poker::{LookupEvaluator, PokerCard, PokerCategory};
+
+// Deals and ranks standard 7 cards poker hands after the puzzle
+const POKER: bool = false;
+const POKER_HANDS: usize = 1_000_000;
 
 fn main()
 {
     let file = File::open("./day-07/input.txt").unwrap();
     let buffer = BufReader::new(file);
 
-    let mut bids = vec![];
+    let lines = buffer
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let result  = total_winnings(&lines, RuleSet::standard());
+    let result2 = total_winnings(&lines, RuleSet::jokers());
+
+    println!("Result: {0}", result);
+    println!("Result 2: {0}", result2);
+
+    let jokers = Rc::new(RuleSet::jokers());
+    let strongest = lines
+        .iter()
+        .map(|line| Bid::with_rules(line, jokers.clone()).hand)
+        .max()
+        .unwrap();
 
-    for line in buffer.lines()
+    println!("Strongest hand with jokers: {0} ({1:?})", strongest.as_string(), strongest.category());
+
+    if POKER
     {
-        match line
+        for (category, count) in poker_categories(POKER_HANDS)
         {
-            Ok(content) => bids.push(Bid::from(content.as_str())),
-            Err(e) => println!("Error while reading file: {:?}", e)
+            println!("{0:?}: {1}", category, count);
         }
     }
+}
 
-    bids.sort_by(|a, b| a.hand.cmp(&b.hand));
+// Number of hands in each category among pseudo random 7 cards hands, ranked with the lookup tables
+fn poker_categories(n_hands: usize) -> Vec<(PokerCategory, usize)>
+{
+    let deck_content = "23456789TJQKA"
+        .chars()
+        .flat_map(|card| "cdhs".chars().map(move |suit| format!("{0}{1}", card, suit)))
+        .collect::<Vec<_>>()
+        .join(" ");
 
-    let result: u32 = bids
-        .iter().enumerate()
-        .map(|(i, b)| ((i + 1) as u32) * b.amount)
-        .sum();
+    let deck: [PokerCard; 52] = poker::parse_hand(&deck_content);
+
+    // Linear congruential generator, its low bits cycle too fast so cards are picked with the high ones
+    let mut seed: u64 = 7;
+
+    let hands = (0..n_hands)
+        .map(|_| {
+            let mut picked: Vec<PokerCard> = vec![];
+
+            while picked.len() < 7
+            {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+                let card = deck[(seed >> 33) as usize % 52];
+                if !picked.contains(&card) { picked.push(card) }
+            }
+
+            picked.try_into().unwrap()
+        })
+        .collect::<Vec<[PokerCard; 7]>>();
+
+    let ranks = LookupEvaluator::new().evaluate_many(&hands);
 
-    for (i, bid) in bids.iter().enumerate()
+    // The tables must agree with the direct evaluation
+    if let Some(hand) = hands.first()
     {
-        println!("[{0}] {1} = {2}", i, &bid.hand.as_string(), bid.hand.value());
+        assert_eq!(poker::evaluate_7(hand), ranks[0], "Lookup tables disagree on {0:?}", hand);
     }
 
-    println!("Result: {0}", result);
-    println!("Result 2: {0}", 0);
+    let mut counts: Vec<(PokerCategory, usize)> = vec![];
+
+    for rank in ranks
+    {
+        match counts.iter_mut().find(|(category, _)| *category == rank.category())
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((rank.category(), 1))
+        }
+    }
+
+    counts.sort();
+
+    counts
+}
+
+fn total_winnings(lines: &[String], rules: RuleSet) -> u32
+{
+    let rules = Rc::new(rules);
+
+    let mut bids = lines
+        .iter()
+        .map(|line| Bid::with_rules(line, rules.clone()))
+        .collect::<Vec<_>>();
+
+    bids.sort_by(|a, b| a.hand.cmp(&b.hand));
+
+    return bids
+        .iter().enumerate()
+        .map(|(i, b)| ((i + 1) as u32) * b.amount)
+        .sum();
 }
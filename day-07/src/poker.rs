@@ -0,0 +1,392 @@
+use std::{fmt::{Formatter, Error}, rc::Rc};
+
+use crate::camel_poker::{Card, RuleSet};
+
+const N_RANKS: usize = 13;
+
+thread_local!
+{
+    // Card ranks follow the standard order, shared by every card
+    static STANDARD_RULES: Rc<RuleSet> = Rc::new(RuleSet::standard());
+}
+
+// Ace can also be the lowest card of a straight (A, 5, 4, 3, 2)
+const WHEEL_MASK: u16 = 0b1_0000_0000_1111;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suit
+{
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PokerCategory
+{
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PokerCard
+{
+    // 0 for '2' up to 12 for 'A'
+    pub rank: u8,
+    pub suit: Suit
+}
+
+// Category in the high bits, then tie-breaking ranks (4 bits each), so ranks compare as integers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandRank(u32);
+
+pub struct LookupEvaluator
+{
+    // Indexed by the sorted ranks of the hand in base 13
+    ranks_table: Vec<u32>,
+    // Indexed by the bit mask of ranks when all cards share the same suit
+    flush_table: Vec<u32>
+}
+
+impl PokerCard
+{
+    pub fn new(card: &Card, suit: Suit) -> Self
+    {
+        // Cards order starts with the strongest one
+        let rank = STANDARD_RULES.with(|rules| rules.cards_order.len() - 1 - rules.strength(card)) as u8;
+
+        PokerCard { rank, suit }
+    }
+
+    pub fn card(&self) -> Card
+    {
+        STANDARD_RULES.with(|rules| Card(rules.cards_order[rules.cards_order.len() - 1 - self.rank as usize]))
+    }
+}
+
+impl HandRank
+{
+    pub fn category(&self) -> PokerCategory
+    {
+        return match self.0 >> 20
+        {
+            0 => PokerCategory::HighCard,
+            1 => PokerCategory::OnePair,
+            2 => PokerCategory::TwoPair,
+            3 => PokerCategory::ThreeOfAKind,
+            4 => PokerCategory::Straight,
+            5 => PokerCategory::Flush,
+            6 => PokerCategory::FullHouse,
+            7 => PokerCategory::FourOfAKind,
+            _ => PokerCategory::StraightFlush
+        }
+    }
+}
+
+pub fn evaluate_5(cards: &[PokerCard; 5]) -> HandRank
+{
+    let ranks = cards.map(|c| c.rank);
+    let flush = cards.iter().all(|c| c.suit == cards[0].suit);
+
+    return HandRank(score(&ranks, flush));
+}
+
+// Best 5 cards hand among the 7 cards
+pub fn evaluate_7(cards: &[PokerCard; 7]) -> HandRank
+{
+    return best_of_7(cards, evaluate_5);
+}
+
+fn best_of_7<F>(cards: &[PokerCard; 7], evaluate: F) -> HandRank
+    where F: Fn(&[PokerCard; 5]) -> HandRank
+{
+    let mut best = HandRank(0);
+
+    // Drop 2 cards out of 7 for each of the 21 combinations
+    for skip_a in 0..7
+    {
+        for skip_b in (skip_a + 1)..7
+        {
+            let mut hand = [cards[0]; 5];
+            let mut i = 0;
+
+            for (j, card) in cards.iter().enumerate()
+            {
+                if j == skip_a || j == skip_b { continue }
+
+                hand[i] = *card;
+                i += 1;
+            }
+
+            best = best.max(evaluate(&hand));
+        }
+    }
+
+    return best;
+}
+
+fn score(ranks: &[u8; 5], flush: bool) -> u32
+{
+    let mut counts = [0u8; N_RANKS];
+    let mut mask = 0u16;
+
+    for rank in ranks
+    {
+        counts[*rank as usize] += 1;
+        mask |= 1 << rank;
+    }
+
+    let straight_high = if mask == WHEEL_MASK
+    {
+        Some(3)
+    }
+    else if mask.count_ones() == 5 && mask >> mask.trailing_zeros() == 0b1_1111
+    {
+        Some(15 - mask.leading_zeros() as u8)
+    }
+    else
+    {
+        None
+    };
+
+    // Biggest groups first, then highest ranks
+    let mut groups = (0..N_RANKS as u8)
+        .rev()
+        .filter(|r| counts[*r as usize] > 0)
+        .map(|r| (counts[r as usize], r))
+        .collect::<Vec<_>>();
+
+    groups.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let category = match (straight_high, flush, groups[0].0, groups.get(1).map(|g| g.0))
+    {
+        (Some(_), true, _, _)    => PokerCategory::StraightFlush,
+        (_, _, 4, _)             => PokerCategory::FourOfAKind,
+        (_, _, 3, Some(2))       => PokerCategory::FullHouse,
+        (_, true, _, _)          => PokerCategory::Flush,
+        (Some(_), _, _, _)       => PokerCategory::Straight,
+        (_, _, 3, _)             => PokerCategory::ThreeOfAKind,
+        (_, _, 2, Some(2))       => PokerCategory::TwoPair,
+        (_, _, 2, _)             => PokerCategory::OnePair,
+        _                        => PokerCategory::HighCard
+    };
+
+    let tie_break = match (category, straight_high)
+    {
+        (PokerCategory::Straight | PokerCategory::StraightFlush, Some(high)) => vec![high],
+        _ => groups.iter().map(|g| g.1).collect()
+    };
+
+    return tie_break
+        .iter()
+        .enumerate()
+        .fold((category as u32) << 20, |acc, (i, rank)| acc | (*rank as u32) << (16 - 4 * i));
+}
+
+impl LookupEvaluator
+{
+    pub fn new() -> Self
+    {
+        let mut ranks_table = vec![0; N_RANKS.pow(5)];
+        let mut flush_table = vec![0; 1 << N_RANKS];
+
+        // Every multiset of 5 ranks (sorted), some are impossible (5 of a kind) but harmless
+        for a in 0..N_RANKS as u8
+        {
+            for b in a..N_RANKS as u8
+            {
+                for c in b..N_RANKS as u8
+                {
+                    for d in c..N_RANKS as u8
+                    {
+                        for e in d..N_RANKS as u8
+                        {
+                            let ranks = [a, b, c, d, e];
+
+                            ranks_table[ranks_index(&ranks)] = score(&ranks, false);
+
+                            if a < b && b < c && c < d && d < e
+                            {
+                                flush_table[ranks_mask(&ranks) as usize] = score(&ranks, true);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        LookupEvaluator { ranks_table, flush_table }
+    }
+
+    pub fn evaluate_5(&self, cards: &[PokerCard; 5]) -> HandRank
+    {
+        let mut ranks = cards.map(|c| c.rank);
+
+        if cards.iter().all(|c| c.suit == cards[0].suit)
+        {
+            return HandRank(self.flush_table[ranks_mask(&ranks) as usize]);
+        }
+
+        ranks.sort_unstable();
+
+        return HandRank(self.ranks_table[ranks_index(&ranks)]);
+    }
+
+    pub fn evaluate_7(&self, cards: &[PokerCard; 7]) -> HandRank
+    {
+        return best_of_7(cards, |hand| self.evaluate_5(hand));
+    }
+
+    pub fn evaluate_many(&self, hands: &[[PokerCard; 7]]) -> Vec<HandRank>
+    {
+        return hands
+            .iter()
+            .map(|hand| self.evaluate_7(hand))
+            .collect();
+    }
+}
+
+fn ranks_index(sorted_ranks: &[u8; 5]) -> usize
+{
+    return sorted_ranks
+        .iter()
+        .fold(0, |acc, rank| acc * N_RANKS + *rank as usize);
+}
+
+fn ranks_mask(ranks: &[u8; 5]) -> u16
+{
+    return ranks
+        .iter()
+        .fold(0, |acc, rank| acc | 1 << rank);
+}
+
+impl From<&str> for PokerCard
+{
+    // "Ah", "Td", "2c"...
+    fn from(value: &str) -> Self
+    {
+        let mut chars = value.chars();
+
+        let card = Card(chars.next().unwrap());
+        let suit = match chars.next()
+        {
+            Some('c') => Suit::Clubs,
+            Some('d') => Suit::Diamonds,
+            Some('h') => Suit::Hearts,
+            Some('s') => Suit::Spades,
+            _ => panic!("Invalid card {0}", value)
+        };
+
+        PokerCard::new(&card, suit)
+    }
+}
+
+impl std::fmt::Debug for PokerCard
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error>
+    {
+        let suit = match self.suit
+        {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's'
+        };
+
+        write!(f, "{0}{1}", self.card().0, suit)
+    }
+}
+
+pub fn parse_hand<const N: usize>(value: &str) -> [PokerCard; N]
+{
+    return value
+        .split_whitespace()
+        .map(PokerCard::from)
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap_or_else(|_| panic!("A hand must be {0} cards", N));
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{parse_hand, evaluate_5, evaluate_7, LookupEvaluator, PokerCategory, PokerCard, Suit};
+
+    #[test]
+    fn categories()
+    {
+        let category = |hand: &str| evaluate_5(&parse_hand(hand)).category();
+
+        assert_eq!(category("Ah Kh Qh Jh Th"), PokerCategory::StraightFlush);
+        assert_eq!(category("9c 9d 9h 9s 2c"), PokerCategory::FourOfAKind);
+        assert_eq!(category("9c 9d 9h 2s 2c"), PokerCategory::FullHouse);
+        assert_eq!(category("2h 7h 9h Jh Kh"), PokerCategory::Flush);
+        assert_eq!(category("Ac 2d 3h 4s 5c"), PokerCategory::Straight);
+        assert_eq!(category("9c 9d 9h 3s 2c"), PokerCategory::ThreeOfAKind);
+        assert_eq!(category("9c 9d 3h 3s 2c"), PokerCategory::TwoPair);
+        assert_eq!(category("9c 9d 4h 3s 2c"), PokerCategory::OnePair);
+        assert_eq!(category("9c Kd 4h 3s 2c"), PokerCategory::HighCard);
+    }
+
+    #[test]
+    fn compare()
+    {
+        let rank = |hand: &str| evaluate_5(&parse_hand(hand));
+
+        // Wheel is the lowest straight
+        assert!(rank("Ac 2d 3h 4s 5c") < rank("2c 3d 4h 5s 6c"));
+        assert!(rank("Kc Kd 3h 3s 2c") > rank("Qc Qd Jh Js Ac"));
+        assert!(rank("Kc Kd 3h 3s 4c") > rank("Kh Ks 3c 3d 2c"));
+        assert_eq!(rank("Kc Kd 3h 3s 4c"), rank("Kh Ks 3c 3d 4h"));
+    }
+
+    #[test]
+    fn best_of_seven()
+    {
+        let hand = parse_hand("Ah Kh 2c 3d Qh Jh Th");
+
+        assert_eq!(evaluate_7(&hand).category(), PokerCategory::StraightFlush);
+        assert_eq!(evaluate_7(&parse_hand("2c 2d 5h 5s 5c 9d 9h")).category(), PokerCategory::FullHouse);
+    }
+
+    #[test]
+    fn lookup_matches_direct_evaluation()
+    {
+        let evaluator = LookupEvaluator::new();
+        let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+        let deck = (0..52)
+            .map(|i| PokerCard { rank: (i % 13) as u8, suit: suits[i / 13] })
+            .collect::<Vec<_>>();
+
+        // Pseudo random hands picked through the deck
+        let hands = (0..2000usize)
+            .map(|i| {
+                let mut picked: Vec<PokerCard> = vec![];
+                let mut j = i.wrapping_mul(7919);
+
+                while picked.len() < 7
+                {
+                    let card = deck[j % 52];
+                    if !picked.contains(&card) { picked.push(card) }
+
+                    j = j.wrapping_mul(31).wrapping_add(17);
+                }
+
+                picked.try_into().unwrap()
+            })
+            .collect::<Vec<[PokerCard; 7]>>();
+
+        let expected = hands.iter().map(evaluate_7).collect::<Vec<_>>();
+
+        assert_eq!(evaluator.evaluate_many(&hands), expected);
+    }
+}
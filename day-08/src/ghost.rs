@@ -0,0 +1,227 @@
+use cycle::{Simulation, Strategy};
+
+use crate::network::Network;
+
+// A single ghost walking the network
+#[derive(Clone)]
+struct GhostWalk<'a>
+{
+    network: &'a Network,
+    node: usize,
+    step: u64
+}
+
+impl Simulation for GhostWalk<'_>
+{
+    // The current node and where we are in the instructions
+    type Fingerprint = (usize, usize);
+
+    fn step(&mut self)
+    {
+        self.node = self.network.step(self.node, self.instruction_index());
+        self.step += 1;
+    }
+
+    fn fingerprint(&self) -> Self::Fingerprint
+    {
+        (self.node, self.instruction_index())
+    }
+}
+
+impl GhostWalk<'_>
+{
+    fn instruction_index(&self) -> usize
+    {
+        (self.step % self.network.instructions_len() as u64) as usize
+    }
+}
+
+// Path of a ghost seen as: a tail of `tail` steps, then a loop of `length` steps repeated forever
+#[derive(Debug, PartialEq, Eq)]
+pub struct GhostCycle
+{
+    pub tail: u64,
+    pub length: u64,
+
+    // Steps (>= 1) in the tail where the ghost is on an end node
+    pub tail_ends: Vec<u64>,
+    // Steps in [tail, tail + length) where the ghost is on an end node, repeated every `length` steps
+    pub cycle_ends: Vec<u64>
+}
+
+impl GhostCycle
+{
+    pub fn analyze(network: &Network, start: usize) -> Self
+    {
+        let walk = GhostWalk { network, node: start, step: 0 };
+        let cycle = cycle::detect(&walk, Strategy::HashMap);
+
+        let (tail, length) = (cycle.start as u64, cycle.length as u64);
+
+        let mut ends = vec![];
+        let mut current = walk;
+
+        while current.step < tail + length
+        {
+            if network.node(current.node).is_end() { ends.push(current.step) }
+            current.step();
+        }
+
+        GhostCycle {
+            tail,
+            length,
+
+            tail_ends: ends.iter().filter(|s| **s >= 1 && **s < tail).copied().collect(),
+            cycle_ends: ends.into_iter().filter(|s| *s >= tail).collect()
+        }
+    }
+
+    pub fn is_end_at(&self, step: u64) -> bool
+    {
+        if step < self.tail { return self.tail_ends.contains(&step) }
+
+        return self.cycle_ends
+            .iter()
+            .any(|end| step.abs_diff(*end) % self.length == 0);
+    }
+}
+
+// Smallest step (>= 1) where every ghost is on an end node, if any
+pub fn first_common_end(cycles: &[GhostCycle]) -> Option<u64>
+{
+    if cycles.is_empty() { return None }
+
+    // Candidates in the tail of one of the ghosts
+    let tail_candidate = cycles
+        .iter()
+        .flat_map(|c| c.tail_ends.iter())
+        .filter(|step| cycles.iter().all(|c| c.is_end_at(**step)))
+        .min()
+        .copied();
+
+    // Once every ghost is in its loop, combine each choice of end offsets with the CRT
+    let min_step = cycles.iter().map(|c| c.tail).max().unwrap_or(0).max(1);
+    let mut combinations: Vec<(i128, i128)> = vec![(0, 1)];
+
+    for cycle in cycles
+    {
+        combinations = combinations
+            .iter()
+            .flat_map(|combination| cycle.cycle_ends
+                .iter()
+                .filter_map(|end| crt(*combination, (*end as i128, cycle.length as i128))))
+            .collect();
+    }
+
+    let cycle_candidate = combinations
+        .into_iter()
+        .map(|(residue, modulus)| {
+            let min_step = min_step as i128;
+            let shift = (min_step - residue).max(0);
+
+            // First value >= min_step in the residue class
+            (residue + (shift + modulus - 1) / modulus * modulus) as u64
+        })
+        .min();
+
+    return match (tail_candidate, cycle_candidate)
+    {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b)
+    };
+}
+
+// Generalized chinese remainder theorem (moduli don't need to be coprime)
+fn crt(a: (i128, i128), b: (i128, i128)) -> Option<(i128, i128)>
+{
+    let (g, p, _) = extended_gcd(a.1, b.1);
+
+    if (b.0 - a.0) % g != 0 { return None }
+
+    let lcm = a.1 / g * b.1;
+    let k = ((b.0 - a.0) / g * p).rem_euclid(b.1 / g);
+
+    return Some(((a.0 + a.1 * k).rem_euclid(lcm), lcm));
+}
+
+// (gcd, x, y) such as a * x + b * y = gcd
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128)
+{
+    if b == 0 { return (a, 1, 0) }
+
+    let (g, x, y) = extended_gcd(b, a % b);
+
+    return (g, y, x - (a / b) * y);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::network::tests::network;
+    use super::{GhostCycle, first_common_end, crt};
+
+    #[test]
+    fn test_crt()
+    {
+        assert_eq!(crt((2, 3), (3, 5)), Some((8, 15)));
+        assert_eq!(crt((1, 4), (3, 6)), Some((9, 12)));
+        assert_eq!(crt((0, 4), (1, 6)), None);
+    }
+
+    #[test]
+    fn test_first_common_end()
+    {
+        // Cycles aligned with their first hit, plain lcm
+        let a = GhostCycle { tail: 1, length: 2, tail_ends: vec![], cycle_ends: vec![2] };
+        let b = GhostCycle { tail: 1, length: 3, tail_ends: vec![], cycle_ends: vec![3] };
+
+        assert_eq!(first_common_end(&[a, b]), Some(6));
+
+        // First hit is not aligned with the cycle
+        let a = GhostCycle { tail: 2, length: 4, tail_ends: vec![], cycle_ends: vec![3] };
+        let b = GhostCycle { tail: 0, length: 5, tail_ends: vec![], cycle_ends: vec![2] };
+
+        assert_eq!(first_common_end(&[a, b]), Some(7));
+
+        // Hit in the tail only
+        let a = GhostCycle { tail: 5, length: 2, tail_ends: vec![4], cycle_ends: vec![] };
+        let b = GhostCycle { tail: 0, length: 2, tail_ends: vec![], cycle_ends: vec![0] };
+
+        assert_eq!(first_common_end(&[a, b]), Some(4));
+
+        // Never at the same time
+        let a = GhostCycle { tail: 0, length: 2, tail_ends: vec![], cycle_ends: vec![0] };
+        let b = GhostCycle { tail: 0, length: 4, tail_ends: vec![], cycle_ends: vec![1] };
+
+        assert_eq!(first_common_end(&[a, b]), None);
+
+        // No ghost at all
+        assert_eq!(first_common_end(&[]), None);
+    }
+
+    #[test]
+    fn test_analyze()
+    {
+        // 11A -> 11B -> 11Z -> 11C -> 11B ...
+        // 22A -> 22Z -> 22B -> 23Z -> 22C -> 22A ...
+        let network = network("L", "11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11C, 11C)
+11C = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (23Z, 23Z)
+23Z = (22C, 22C)
+22C = (22A, 22A)");
+
+        let first = GhostCycle::analyze(&network, network.index("11A").unwrap());
+        let second = GhostCycle::analyze(&network, network.index("22A").unwrap());
+
+        assert_eq!(first, GhostCycle { tail: 1, length: 3, tail_ends: vec![], cycle_ends: vec![2] });
+        // Two ends in the same cycle
+        assert_eq!(second, GhostCycle { tail: 0, length: 5, tail_ends: vec![], cycle_ends: vec![1, 3] });
+
+        // 8 is 2 in the first cycle and 3 in the second one
+        assert_eq!(first_common_end(&[first, second]), Some(8));
+    }
+}
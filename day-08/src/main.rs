@@ -1,5 +1,10 @@
 use std::{fs::File, io::{BufReader, BufRead}, collections::HashMap};
-use num::integer::lcm;
+
+mod ghost;
+mod network;
+
+use ghost::{GhostCycle, first_common_end};
+use network::Network;
 
 const START_NODE: &str = "AAA";
 const END_NODE: &str = "ZZZ";
@@ -31,78 +36,37 @@ fn main()
     let buffer = BufReader::new(file);
 
     let (instructions, nodes) = parse(buffer);
+    let network = Network::new(&instructions, nodes);
 
-    let result = result_part_1(&instructions, &nodes);
-    println!("Result: {0}", result);
+    match result_part_1(&network)
+    {
+        Some(result) => println!("Result: {0}", result),
+        None => println!("Result: {0} is never reached", END_NODE)
+    }
 
-    let result2 = result_part_2(&instructions, &nodes);
-    println!("Result2: {0}", result2);
+    match result_part_2(&network)
+    {
+        Some(result2) => println!("Result2: {0}", result2),
+        None => println!("Result2: ghosts are never on end nodes at the same time")
+    }
 }
 
-fn result_part_1(instructions: &Vec<Instruction>, nodes: &HashMap<Node, (Node, Node)>) -> u32
+fn result_part_1(network: &Network) -> Option<u64>
 {
-    let mut step: u32 = 0;
-    let mut current_node = &Node(START_NODE.to_string());
-
-    for instruction in instructions.iter().cycle()
-    {
-        let node = nodes.get(current_node).unwrap();
-
-        current_node = match instruction
-        {
-            Instruction::Left => &node.0,
-            Instruction::Right => &node.1
-        };
-
-        step += 1;
-
-        if current_node == &Node(END_NODE.to_string())
-        {
-            break;
-        }
-    }
+    let start = network.index(START_NODE)?;
+    let targets = network.nodes_where(|n| n.0 == END_NODE);
 
-    step
+    return network.steps_to(start, &targets);
 }
 
-fn result_part_2(instructions: &Vec<Instruction>, nodes: &HashMap<Node, (Node, Node)>) -> u64
+fn result_part_2(network: &Network) -> Option<u64>
 {
-    let mut smallest_steps : Vec<u64> = vec![];
-
-    let starting_nodes : Vec<&Node> = nodes.keys()
-        .filter(|n| n.is_start())
+    let cycles : Vec<GhostCycle> = (0..network.len())
+        .filter(|n| network.node(*n).is_start())
+        .map(|n| GhostCycle::analyze(network, n))
         .collect();
 
-    for starting_node in starting_nodes
-    {
-        let mut step: u64 = 0;
-        let mut current_node = starting_node;
-
-        for instruction in instructions.iter().cycle()
-        {
-            let node = nodes.get(current_node).unwrap();
-
-            current_node = match instruction
-            {
-                Instruction::Left => &node.0,
-                Instruction::Right => &node.1
-            };
-
-            step += 1;
-
-            if current_node.is_end()
-            {
-                break;
-            }
-        }
-
-        smallest_steps.push(step);
-    }
-
-    let init = smallest_steps[0].clone() as u64;
-    let lcm = smallest_steps.into_iter().fold(init, |acc, step| lcm(acc, step));
-
-    return lcm;
+    return first_common_end(&cycles);
 }
 
 fn parse(buffer: BufReader<File>) -> (Vec<Instruction>, HashMap<Node, (Node, Node)>)
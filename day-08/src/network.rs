@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::{Instruction, Node};
+
+// Network compiled into indexes, so walking it is only array lookups
+pub struct Network
+{
+    nodes: Vec<Node>,
+    indexes: HashMap<String, usize>,
+
+    // Left and right node index for each node
+    edges: Vec<[usize; 2]>,
+    // 0 for left, 1 for right
+    instructions: Vec<usize>,
+    // Node reached after running the whole instruction string from each node
+    jumps: Vec<usize>
+}
+
+impl Network
+{
+    pub fn new(instructions: &[Instruction], nodes: HashMap<Node, (Node, Node)>) -> Self
+    {
+        let mut names: Vec<&Node> = nodes.keys().collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let indexes: HashMap<String, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.0.clone(), i))
+            .collect();
+
+        let index_of = |node: &Node| match indexes.get(&node.0)
+        {
+            Some(index) => *index,
+            None => panic!("Unknown node {0}", node.0)
+        };
+
+        let edges = names
+            .iter()
+            .map(|n| {
+                let (left, right) = nodes.get(n).unwrap();
+                [index_of(left), index_of(right)]
+            })
+            .collect::<Vec<_>>();
+
+        let instructions = instructions
+            .iter()
+            .map(|i| match i
+            {
+                Instruction::Left => 0,
+                Instruction::Right => 1
+            })
+            .collect::<Vec<_>>();
+
+        let jumps = (0..edges.len())
+            .map(|start| instructions.iter().fold(start, |node, i| edges[node][*i]))
+            .collect();
+
+        let mut nodes: Vec<Node> = nodes.into_keys().collect();
+        nodes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Network { nodes, indexes, edges, instructions, jumps }
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.nodes.len()
+    }
+
+    pub fn instructions_len(&self) -> usize
+    {
+        self.instructions.len()
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize>
+    {
+        self.indexes.get(name).copied()
+    }
+
+    pub fn node(&self, index: usize) -> &Node
+    {
+        &self.nodes[index]
+    }
+
+    // Node reached from `node` when running the instruction at `instruction_index`
+    pub fn step(&self, node: usize, instruction_index: usize) -> usize
+    {
+        self.edges[node][self.instructions[instruction_index]]
+    }
+
+    pub fn jump(&self, node: usize) -> usize
+    {
+        self.jumps[node]
+    }
+
+    pub fn nodes_where<F>(&self, predicate: F) -> Vec<bool>
+        where F: Fn(&Node) -> bool
+    {
+        self.nodes.iter().map(predicate).collect()
+    }
+
+    // For each node, first step (1..=instructions_len) of a pass where we are on a target node
+    pub fn first_hits(&self, targets: &[bool]) -> Vec<Option<u32>>
+    {
+        (0..self.len())
+            .map(|start| {
+                let mut node = start;
+
+                (0..self.instructions_len())
+                    .find(|i| {
+                        node = self.step(node, *i);
+                        targets[node]
+                    })
+                    .map(|i| i as u32 + 1)
+            })
+            .collect()
+    }
+
+    // Number of steps until we reach a target node, jumping over whole passes without target
+    pub fn steps_to(&self, start: usize, targets: &[bool]) -> Option<u64>
+    {
+        let first_hits = self.first_hits(targets);
+        let mut node = start;
+
+        // After len() passes without hit, we are looping forever
+        for pass in 0..=self.len() as u64
+        {
+            if let Some(hit) = first_hits[node]
+            {
+                return Some(pass * self.instructions_len() as u64 + hit as u64);
+            }
+
+            node = self.jump(node);
+        }
+
+        return None;
+    }
+}
+
+#[cfg(test)]
+pub mod tests
+{
+    use std::collections::HashMap;
+
+    use crate::{Instruction, parse_node};
+    use super::Network;
+
+    pub fn network(instructions: &str, lines: &str) -> Network
+    {
+        let instructions = instructions
+            .chars()
+            .map(|c| if c == 'L' { Instruction::Left } else { Instruction::Right })
+            .collect::<Vec<_>>();
+
+        let nodes = lines
+            .lines()
+            .map(|l| {
+                let (start, left, right) = parse_node(l.to_string());
+                (start, (left, right))
+            })
+            .collect::<HashMap<_, _>>();
+
+        Network::new(&instructions, nodes)
+    }
+
+    #[test]
+    fn test_steps_to()
+    {
+        let network = network("LLR", "AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)");
+
+        let start = network.index("AAA").unwrap();
+        let targets = network.nodes_where(|n| n.0 == "ZZZ");
+
+        assert_eq!(network.steps_to(start, &targets), Some(6));
+        assert_eq!(network.jump(start), network.index("BBB").unwrap());
+    }
+
+    #[test]
+    fn test_unreachable()
+    {
+        let network = network("LR", "AAA = (BBB, BBB)
+BBB = (AAA, AAA)
+ZZZ = (ZZZ, ZZZ)");
+
+        let start = network.index("AAA").unwrap();
+        let targets = network.nodes_where(|n| n.0 == "ZZZ");
+
+        assert_eq!(network.steps_to(start, &targets), None);
+    }
+}
@@ -11,18 +11,33 @@ fn main()
 
     let histories = parse(buffer);
 
-    let result: i64 = histories
+    let result: Option<i64> = histories
         .iter()
         .map(|h| h.next_value())
         .sum();
 
-    let result2: i64 = histories
+    let result2: Option<i64> = histories
         .iter()
         .map(|h| h.previous_value())
         .sum();
 
-    println!("Result: {:?}", result);
-    println!("Result 2: {:?}", result2);
+    match (result, result2)
+    {
+        (Some(result), Some(result2)) =>
+        {
+            println!("Result: {:?}", result);
+            println!("Result 2: {:?}", result2);
+
+            // Number of difference rows before the constant one, over all histories
+            let degree = histories
+                .iter()
+                .filter_map(|h| h.polynomial()?.degree())
+                .max();
+
+            println!("Highest degree: {:?}", degree);
+        },
+        _ => println!("Some histories never reach an all-zero difference row")
+    }
 }
 
 fn parse(buffer: BufReader<File>) -> Vec<History>
@@ -3,35 +3,98 @@ pub struct History
     values: Vec<i64>
 }
 
-fn next_value_in_sequence(values: &Vec<i64>) -> i64
+// Newton form of the interpolating polynomial: p(x) = sum(coefficients[k] * C(x, k))
+// where x is the index in the history and coefficients[k] the k-th difference at index 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial
 {
-    if values.iter().all(|v| *v == 0) { return 0 }
+    coefficients: Vec<i128>
+}
+
+impl Polynomial
+{
+    // None if the differences never reach an all-zero row, ie. not enough values to know the polynomial
+    // (so a single non zero value is not taken as a constant, it takes two equal values)
+    pub fn from_values(values: &[i64]) -> Option<Self>
+    {
+        let mut coefficients = vec![];
+        let mut row: Vec<i128> = values.iter().map(|v| *v as i128).collect();
+
+        while !row.iter().all(|v| *v == 0)
+        {
+            coefficients.push(row[0]);
+
+            // Differences of i64 values can go past i64, and past i128 for very long histories
+            row = row.windows(2)
+                .map(|w| w[1].checked_sub(w[0]))
+                .collect::<Option<_>>()?;
+        }
+
+        // Empty history, or last row is a single non zero value
+        if row.is_empty() { return None }
 
-    let mut diff_seq = vec![];
+        // Trailing zero differences don't change the polynomial
+        while coefficients.last() == Some(&0) { coefficients.pop(); }
 
-    for i in 0..values.len()-1
+        Some(Polynomial { coefficients })
+    }
+
+    // None for the zero polynomial
+    pub fn degree(&self) -> Option<usize>
     {
-        diff_seq.push(values[i+1] - values[i])
+        self.coefficients.len().checked_sub(1)
     }
 
-    values.last().unwrap() + next_value_in_sequence(&diff_seq)
+    // Works for any index, negative ones included. None if the value does not fit in an i128
+    pub fn evaluate(&self, x: i64) -> Option<i128>
+    {
+        let x = x as i128;
+
+        let mut binomial: i128 = 1;
+        let mut result: i128 = 0;
+
+        for (k, coefficient) in self.coefficients.iter().enumerate()
+        {
+            if k > 0
+            {
+                // C(x, k) = C(x, k - 1) * (x - k + 1) / k, always an exact division
+                binomial = binomial.checked_mul(x - k as i128 + 1)? / k as i128;
+            }
+
+            result = result.checked_add(binomial.checked_mul(*coefficient)?)?;
+        }
+
+        Some(result)
+    }
+}
+
+fn next_value_in_sequence(values: &[i64]) -> Option<i64>
+{
+    let polynomial = Polynomial::from_values(values)?;
+
+    polynomial.evaluate(values.len() as i64)?.try_into().ok()
 }
 
 impl History
 {
-    pub fn next_value(&self) -> i64
+    pub fn polynomial(&self) -> Option<Polynomial>
     {
-        return next_value_in_sequence(&self.values);
+        Polynomial::from_values(&self.values)
     }
 
-    pub fn previous_value(&self) -> i64
+    pub fn value_at(&self, index: i64) -> Option<i128>
     {
-        let rev_values = self.values.iter()
-            .rev()
-            .map(|v| v.clone())
-            .collect();
+        self.polynomial()?.evaluate(index)
+    }
 
-        next_value_in_sequence(&rev_values)
+    pub fn next_value(&self) -> Option<i64>
+    {
+        return next_value_in_sequence(&self.values);
+    }
+
+    pub fn previous_value(&self) -> Option<i64>
+    {
+        self.value_at(-1)?.try_into().ok()
     }
 }
 
@@ -56,7 +119,7 @@ mod tests
     fn test_next_value_in_sequence()
     {
         let initial_seq = vec![1, 3, 6, 10, 15, 21];
-        assert_eq!(next_value_in_sequence(&initial_seq), 28)
+        assert_eq!(next_value_in_sequence(&initial_seq), Some(28))
     }
 
     #[test]
@@ -65,7 +128,7 @@ mod tests
         let mut seq: Vec<i64> = vec![10, 13, 16, 21, 30, 45];
         seq.reverse();
 
-        assert_eq!(next_value_in_sequence(&seq), 5);
+        assert_eq!(next_value_in_sequence(&seq), Some(5));
     }
 
     #[test]
@@ -77,9 +140,65 @@ mod tests
             History::from("10 13 16 21 30 45".to_string())
         ];
 
-        let next_values: Vec<i64> = histories.iter().map(|h| h.next_value()).collect();
+        let next_values: Vec<i64> = histories.iter().map(|h| h.next_value().unwrap()).collect();
         let result: i64 = next_values.iter().sum();
 
         assert_eq!(result, 114);
     }
+
+    #[test]
+    fn test_polynomial()
+    {
+        let history = History::from("1 3 6 10 15 21".to_string());
+        let polynomial = history.polynomial().unwrap();
+
+        assert_eq!(polynomial.degree(), Some(2));
+
+        // Triangular numbers, x = 0 being the first value
+        assert_eq!(history.value_at(-1), Some(0));
+        assert_eq!(history.value_at(-2), Some(0));
+        assert_eq!(history.value_at(-3), Some(1));
+        assert_eq!(history.value_at(1_000_000), Some(1_000_001 * 1_000_002 / 2));
+
+        assert_eq!(History::from("10 13 16 21 30 45".to_string()).previous_value(), Some(5));
+        assert_eq!(History::from("7 7 7".to_string()).polynomial().unwrap().degree(), Some(0));
+        assert_eq!(History::from("0 0".to_string()).polynomial().unwrap().degree(), None);
+
+        // A single value only reaches the zero row when it is 0
+        assert_eq!(History::from("5".to_string()).polynomial(), None);
+        assert_eq!(History::from("0".to_string()).previous_value(), Some(0));
+    }
+
+    #[test]
+    fn test_two_values()
+    {
+        // Same rule as longer histories, the last difference must be 0
+        assert_eq!(History::from("3 5".to_string()).polynomial(), None);
+        assert_eq!(History::from("3 5".to_string()).next_value(), None);
+
+        assert_eq!(History::from("3 3".to_string()).polynomial().unwrap().degree(), Some(0));
+        assert_eq!(History::from("3 3".to_string()).next_value(), Some(3));
+        assert_eq!(History::from("3 3".to_string()).previous_value(), Some(3));
+    }
+
+    #[test]
+    fn test_differences_past_i64()
+    {
+        // Symmetric quadratic, first differences are +-(2^64 - 1)
+        let history = History { values: vec![i64::MIN, i64::MAX, i64::MAX, i64::MIN] };
+
+        assert_eq!(history.polynomial().unwrap().degree(), Some(2));
+        assert_eq!(history.value_at(-1), history.value_at(4));
+        assert_eq!(history.value_at(4), Some(i64::MIN as i128 - 2 * u64::MAX as i128));
+
+        // Next value doesn't fit in an i64
+        assert_eq!(history.next_value(), None);
+    }
+
+    #[test]
+    fn test_never_reaching_zero()
+    {
+        assert_eq!(History::from("1 2 4 8".to_string()).polynomial(), None);
+        assert_eq!(next_value_in_sequence(&[]), None);
+    }
 }
@@ -0,0 +1,127 @@
+use geometry::Polygon;
+
+use crate::{Tile, maze};
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Side
+{
+    Loop,
+    Inside,
+    Outside
+}
+
+pub struct LoopAnalysis
+{
+    // Real pipe under the starting point
+    pub start_shape: Tile,
+    // Loop tiles in walking order as (row, column) points, starting with the starting point (not repeated at the end)
+    pub polygon: Polygon
+}
+
+impl LoopAnalysis
+{
+    // None if there is no loop through the starting point
+    pub fn new(matrix: &Vec<Vec<Tile>>) -> Option<Self>
+    {
+        let (start_shape, coords) = maze::find_loop(matrix)?;
+
+        let polygon = Polygon::new(coords.iter().map(|(i, j)| (*i as i64, *j as i64)).collect());
+
+        Some(LoopAnalysis { start_shape, polygon })
+    }
+
+    pub fn farthest_distance(&self) -> usize
+    {
+        self.polygon.vertices.len() / 2
+    }
+
+    // Pick's theorem, every loop tile being a boundary point
+    pub fn enclosed_tiles_count(&self) -> usize
+    {
+        self.polygon.interior_points() as usize
+    }
+
+    // Inside / outside for every tile, only needed for rendering
+    pub fn classify(&self, matrix: &Vec<Vec<Tile>>) -> Vec<Vec<Side>>
+    {
+        let mut sides: Vec<Vec<Side>> = matrix.iter()
+            .map(|row| vec![Side::Outside; row.len()])
+            .collect();
+
+        for (i, j) in &self.polygon.vertices
+        {
+            sides[*i as usize][*j as usize] = Side::Loop;
+        }
+
+        for (i, row) in matrix.iter().enumerate()
+        {
+            let mut inside = false;
+
+            for (j, tile) in row.iter().enumerate()
+            {
+                if sides[i][j] != Side::Loop
+                {
+                    if inside { sides[i][j] = Side::Inside }
+                    continue;
+                }
+
+                let tile = if *tile == Tile::Start { self.start_shape } else { *tile };
+
+                // Crossing the loop only on pipes going north, so └┐ flips once and └┘ twice
+                if let Tile::Vertical | Tile::NorthEast | Tile::NorthWest = tile
+                {
+                    inside = !inside;
+                }
+            }
+        }
+
+        sides
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{parse, Tile};
+    use super::{LoopAnalysis, Side};
+
+    #[test]
+    fn test_enclosed_tiles()
+    {
+        let matrix = parse("...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........".as_bytes());
+
+        let analysis = LoopAnalysis::new(&matrix).unwrap();
+        let sides = analysis.classify(&matrix);
+
+        assert_eq!(analysis.start_shape, Tile::SouthEast);
+        assert_eq!(analysis.enclosed_tiles_count(), 4);
+        assert!(analysis.polygon.is_simple());
+        assert_eq!(sides.iter().flatten().filter(|s| **s == Side::Inside).count(), 4);
+        assert_eq!(sides[6][2], Side::Inside);
+        assert_eq!(sides[3][3], Side::Outside);
+    }
+
+    #[test]
+    fn test_farthest_distance()
+    {
+        let matrix = parse("..F7.
+.FJ|.
+SJ.L7
+|F--J
+LJ...".as_bytes());
+
+        let analysis = LoopAnalysis::new(&matrix).unwrap();
+
+        assert_eq!(analysis.start_shape, Tile::SouthEast);
+        assert_eq!(analysis.farthest_distance(), 8);
+        assert_eq!(analysis.enclosed_tiles_count(), 1);
+    }
+}
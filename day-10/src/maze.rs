@@ -0,0 +1,399 @@
+use std::collections::VecDeque;
+
+use crate::{Coord, Tile};
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Direction
+{
+    North,
+    South,
+    East,
+    West
+}
+
+// A pipe pointing to a tile that does not point back
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Mismatch
+{
+    pub coord: Coord,
+    pub direction: Direction,
+    // None when the pipe points outside of the grid
+    pub neighboor: Option<(Coord, Tile)>
+}
+
+#[derive(Debug, Default)]
+pub struct MazeReport
+{
+    // Every component where all pipes have exactly 2 connections
+    pub loops: Vec<Vec<Coord>>,
+    // Every other connected pipe segment
+    pub dangling: Vec<Vec<Coord>>,
+    pub mismatches: Vec<Mismatch>
+}
+
+impl Direction
+{
+    pub fn opposite(&self) -> Direction
+    {
+        match self
+        {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East
+        }
+    }
+
+    pub fn between(from: Coord, to: Coord) -> Direction
+    {
+        if to.0 < from.0 { Direction::North }
+        else if to.0 > from.0 { Direction::South }
+        else if to.1 > from.1 { Direction::East }
+        else { Direction::West }
+    }
+
+    pub fn next(&self, coord: Coord, matrix: &Vec<Vec<Tile>>) -> Option<Coord>
+    {
+        let next = match self
+        {
+            Direction::North => (coord.0.checked_sub(1)?, coord.1),
+            Direction::South => (coord.0 + 1, coord.1),
+            Direction::East => (coord.0, coord.1 + 1),
+            Direction::West => (coord.0, coord.1.checked_sub(1)?)
+        };
+
+        if next.0 >= matrix.len() || next.1 >= matrix[next.0].len() { return None }
+
+        Some(next)
+    }
+}
+
+impl Tile
+{
+    // Starting point may connect anywhere
+    pub fn connections(&self) -> Vec<Direction>
+    {
+        match self
+        {
+            Tile::Horizontal => vec![Direction::East, Direction::West],
+            Tile::Vertical => vec![Direction::North, Direction::South],
+            Tile::NorthEast => vec![Direction::North, Direction::East],
+            Tile::NorthWest => vec![Direction::North, Direction::West],
+            Tile::SouthEast => vec![Direction::South, Direction::East],
+            Tile::SouthWest => vec![Direction::South, Direction::West],
+            Tile::Start => vec![Direction::North, Direction::South, Direction::East, Direction::West],
+            Tile::Ground => vec![]
+        }
+    }
+
+    // Pipe connecting both directions, None if they are the same
+    pub fn from_connections(a: Direction, b: Direction) -> Option<Tile>
+    {
+        match (a, b)
+        {
+            (Direction::North, Direction::South) | (Direction::South, Direction::North) => Some(Tile::Vertical),
+            (Direction::East, Direction::West)   | (Direction::West, Direction::East)   => Some(Tile::Horizontal),
+            (Direction::North, Direction::East)  | (Direction::East, Direction::North)  => Some(Tile::NorthEast),
+            (Direction::North, Direction::West)  | (Direction::West, Direction::North)  => Some(Tile::NorthWest),
+            (Direction::South, Direction::East)  | (Direction::East, Direction::South)  => Some(Tile::SouthEast),
+            (Direction::South, Direction::West)  | (Direction::West, Direction::South)  => Some(Tile::SouthWest),
+            _ => None
+        }
+    }
+
+    pub fn as_char(&self) -> char
+    {
+        match self
+        {
+            Tile::Horizontal => '-',
+            Tile::Vertical => '|',
+            Tile::NorthEast => 'L',
+            Tile::NorthWest => 'J',
+            Tile::SouthEast => 'F',
+            Tile::SouthWest => '7',
+            Tile::Start => 'S',
+            Tile::Ground => '.'
+        }
+    }
+}
+
+// Neighboors connected both ways
+pub fn connected_neighboors(coord: Coord, matrix: &Vec<Vec<Tile>>) -> Vec<Coord>
+{
+    matrix[coord.0][coord.1]
+        .connections()
+        .into_iter()
+        .filter_map(|d| {
+            let next = d.next(coord, matrix)?;
+
+            if matrix[next.0][next.1].connections().contains(&d.opposite()) { Some(next) }
+            else { None }
+        })
+        .collect()
+}
+
+// Pipe under the starting point: the one closing a loop through it, None if there is no such loop
+pub fn start_shape(matrix: &Vec<Vec<Tile>>) -> Option<(Coord, Tile)>
+{
+    let start = matrix.iter().enumerate()
+        .find_map(|(i, row)| row.iter().position(|t| *t == Tile::Start).map(|j| (i, j)))?;
+
+    for direction in Tile::Start.connections()
+    {
+        let Some(mut current) = direction.next(start, matrix) else { continue };
+        let mut previous = start;
+
+        // Follow the pipes until we are back to the start, or stuck
+        while current != start
+        {
+            let next = connected_neighboors(current, matrix)
+                .into_iter()
+                .find(|n| *n != previous);
+
+            match next
+            {
+                Some(next) if connected_neighboors(current, matrix).contains(&previous) =>
+                {
+                    previous = current;
+                    current = next;
+                },
+                _ => break
+            }
+        }
+
+        if current != start { continue }
+
+        if let Some(tile) = Tile::from_connections(direction, Direction::between(start, previous))
+        {
+            return Some((start, tile));
+        }
+    }
+
+    None
+}
+
+// Pipe under the starting point and the loop tiles in walking order, starting with the starting point (not repeated at the end)
+pub fn find_loop(matrix: &Vec<Vec<Tile>>) -> Option<(Tile, Vec<Coord>)>
+{
+    let (start, tile) = start_shape(matrix)?;
+
+    let mut coords = vec![start];
+    let mut previous = start;
+    let mut current = tile.connections()[0].next(start, matrix)?;
+
+    // Pipes next to the starting point connect back to it whatever it really is
+    while current != start
+    {
+        coords.push(current);
+
+        let next = connected_neighboors(current, matrix)
+            .into_iter()
+            .find(|n| *n != previous)?;
+
+        previous = current;
+        current = next;
+    }
+
+    Some((tile, coords))
+}
+
+pub fn validate(matrix: &Vec<Vec<Tile>>) -> MazeReport
+{
+    let original = matrix;
+
+    // Starting point only connects the way its loop goes, other pipes touching it are dangling
+    let mut resolved = matrix.clone();
+
+    if let Some((start, tile)) = start_shape(matrix)
+    {
+        resolved[start.0][start.1] = tile;
+    }
+
+    let matrix = &resolved;
+
+    let mut report = MazeReport::default();
+    let mut visited: Vec<Vec<bool>> = matrix.iter().map(|row| vec![false; row.len()]).collect();
+
+    for (i, row) in matrix.iter().enumerate()
+    {
+        for (j, tile) in row.iter().enumerate()
+        {
+            // The starting point is not a real pipe, it can't be wrong
+            if original[i][j] == Tile::Start { continue }
+
+            for direction in tile.connections()
+            {
+                let neighboor = direction
+                    .next((i, j), matrix)
+                    .map(|n| (n, matrix[n.0][n.1]));
+
+                let points_back = neighboor
+                    .map(|(_, t)| t.connections().contains(&direction.opposite()))
+                    .unwrap_or(false);
+
+                if !points_back
+                {
+                    report.mismatches.push(Mismatch { coord: (i, j), direction, neighboor });
+                }
+            }
+        }
+    }
+
+    for (i, row) in matrix.iter().enumerate()
+    {
+        for (j, tile) in row.iter().enumerate()
+        {
+            if *tile == Tile::Ground || visited[i][j] { continue }
+
+            let mut component = vec![];
+            let mut queue = VecDeque::from([(i, j)]);
+            visited[i][j] = true;
+
+            while let Some(coord) = queue.pop_front()
+            {
+                component.push(coord);
+
+                for next in connected_neighboors(coord, matrix)
+                {
+                    if visited[next.0][next.1] { continue }
+
+                    visited[next.0][next.1] = true;
+                    queue.push_back(next);
+                }
+            }
+
+            let is_loop = component.len() > 1 && component
+                .iter()
+                .all(|c| connected_neighboors(*c, matrix).len() == 2);
+
+            component.sort();
+
+            if is_loop { report.loops.push(component) }
+            else { report.dangling.push(component) }
+        }
+    }
+
+    report
+}
+
+// Same grid with every pipe not part of a loop replaced by ground
+pub fn clean(matrix: &Vec<Vec<Tile>>, report: &MazeReport) -> Vec<Vec<Tile>>
+{
+    let mut cleaned: Vec<Vec<Tile>> = matrix.iter()
+        .map(|row| row.iter().map(|t| if *t == Tile::Start { Tile::Start } else { Tile::Ground }).collect())
+        .collect();
+
+    for coord in report.loops.iter().flatten()
+    {
+        cleaned[coord.0][coord.1] = matrix[coord.0][coord.1];
+    }
+
+    cleaned
+}
+
+pub fn to_text(matrix: &Vec<Vec<Tile>>) -> String
+{
+    matrix.iter()
+        .map(|row| row.iter().map(|t| t.as_char()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{parse, Tile};
+    use super::{validate, clean, to_text, start_shape, connected_neighboors, find_loop, Direction};
+
+    const MAZE: &str = "-L|F7
+7S-7|
+L|7||
+-L-J|
+L|-JF";
+
+    #[test]
+    fn test_connected_neighboors()
+    {
+        let matrix = vec![
+            vec![Tile::Ground, Tile::Vertical],
+            vec![Tile::Horizontal, Tile::NorthWest]
+        ];
+
+        assert!(connected_neighboors((0, 0), &matrix).is_empty());
+        assert_eq!(connected_neighboors((1, 1), &matrix), vec![(0, 1), (1, 0)]);
+        // The | only points back to the J, its other end is outside of the grid
+        assert_eq!(connected_neighboors((0, 1), &matrix), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_find_loop()
+    {
+        let matrix = parse(MAZE.as_bytes());
+        let (tile, coords) = find_loop(&matrix).unwrap();
+
+        assert_eq!(tile, Tile::SouthEast);
+        assert_eq!(coords, vec![(1, 1), (2, 1), (3, 1), (3, 2), (3, 3), (2, 3), (1, 3), (1, 2)]);
+        assert!(find_loop(&parse("S-.\n|..".as_bytes())).is_none());
+    }
+
+    #[test]
+    fn test_validate()
+    {
+        let matrix = parse(MAZE.as_bytes());
+        let report = validate(&matrix);
+
+        assert_eq!(report.loops.len(), 1);
+        assert_eq!(report.loops[0].len(), 8);
+        assert!(report.loops[0].contains(&(1, 1)));
+
+        // Pipes pointing outside of the grid
+        assert!(report.mismatches.iter().any(|m| m.coord == (0, 0) && m.direction == Direction::West && m.neighboor.is_none()));
+        // F at (0, 3) pointing down to a 7
+        assert!(report.mismatches.iter().any(|m| m.coord == (0, 3) && m.neighboor == Some(((1, 3), Tile::SouthWest))));
+        assert!(!report.mismatches.iter().any(|m| report.loops[0].contains(&m.coord)));
+    }
+
+    #[test]
+    fn test_clean()
+    {
+        let matrix = parse(MAZE.as_bytes());
+        let report = validate(&matrix);
+
+        assert_eq!(to_text(&clean(&matrix, &report)), ".....
+.S-7.
+.|.|.
+.L-J.
+.....");
+    }
+
+    #[test]
+    fn test_multiple_loops()
+    {
+        let matrix = parse("F7.F7
+LJ.LJ
+..F-.".as_bytes());
+        let report = validate(&matrix);
+
+        assert_eq!(report.loops.len(), 2);
+        assert_eq!(report.dangling, vec![vec![(2, 2), (2, 3)]]);
+    }
+
+    #[test]
+    fn test_start_touching_another_pipe()
+    {
+        // | above S points to it, but S is a F closing the loop
+        let matrix = parse(".|...
+.S-7.
+.|.|.
+.L-J.
+.....".as_bytes());
+        let report = validate(&matrix);
+
+        assert_eq!(start_shape(&matrix), Some(((1, 1), Tile::SouthEast)));
+        assert_eq!(report.loops.len(), 1);
+        assert_eq!(report.loops[0].len(), 8);
+        assert!(report.loops[0].contains(&(1, 1)));
+        assert_eq!(report.dangling, vec![vec![(0, 1)]]);
+        assert!(report.mismatches.iter().any(|m| m.coord == (0, 1) && m.direction == Direction::South));
+    }
+}
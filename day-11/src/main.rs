@@ -2,7 +2,12 @@ use std::{io::BufReader, fs::File};
 
 mod observatory;
 
-use observatory::Space;
+use observatory::{Space, Expansion};
+
+// Switch to true to print the expanded universe
+const RENDER: bool = false;
+// Switch to true to compare the other expansion models
+const MODELS: bool = false;
 
 fn main()
 {
@@ -11,11 +16,37 @@ fn main()
 
     let space = Space::from(buffer);
 
-    let galaxy_pairs = space.find_galaxy_pairs(2);
-    let galaxy_pairs2 = space.find_galaxy_pairs(1_000_000);
+    let result = space.total_distance(2);
+    let result2 = space.total_distance(1_000_000);
+
+    if let Some(((a, b), distance)) = space.farthest_pairs(1, 2).unwrap().first()
+    {
+        println!("Farthest galaxies: {:?} and {:?} ({})", a, b, distance);
+
+        if let Some((nearest, distance)) = space.nearest_galaxy(*a, 2)
+        {
+            println!("Nearest galaxy from {:?}: {:?} ({})", a, nearest, distance);
+        }
+    }
+
+    if MODELS
+    {
+        let models = [
+            Expansion::Axes { rows: 2, columns: 1_000_000 },
+            Expansion::Density(2),
+            Expansion::Repeated(Box::new(Expansion::Uniform(2)), 10)
+        ];
+
+        for model in models
+        {
+            println!("{:?}: {:?}", model, space.total_distance_with(&model));
+        }
+    }
 
-    let result: u64 = galaxy_pairs.iter().map(|pair| pair.1).sum();
-    let result2: u64 = galaxy_pairs2.iter().map(|pair| pair.1).sum();
+    if RENDER
+    {
+        println!("{}", space.expand_with(&Expansion::Uniform(2)).unwrap().render());
+    }
 
     println!("Result: {}", result);
     println!("Result: {}", result2);
@@ -1,4 +1,4 @@
-use std::{io::{BufReader, BufRead}, fs::File, collections::HashSet};
+use std::{io::{BufReader, BufRead}, fs::File, collections::{HashSet, BTreeSet}};
 
 type Coord = (usize, usize);
 
@@ -8,12 +8,38 @@ pub enum Tile
     Galaxy,
 }
 
+#[derive(Debug, Clone)]
+pub enum Expansion
+{
+    // Every void row and column becomes `n` of them
+    Uniform(u64),
+    // Different factors for void rows and void columns
+    Axes { rows: u64, columns: u64 },
+    // Every row and column grows up to `n` times, proportionally to how empty it is
+    Density(u64),
+    // Model applied `n` times in a row, each pass growing the rows already grown
+    Repeated(Box<Expansion>, u32)
+}
+
+// Expanded universe, only galaxies are stored
+#[derive(Debug, PartialEq, Eq)]
+pub struct SparseUniverse
+{
+    pub height: u64,
+    pub width: u64,
+    pub galaxies: BTreeSet<(u64, u64)>
+}
+
 pub struct Space
 {
     matrix: Vec<Vec<Option<Tile>>>,
     galaxies_index: Vec<Coord>,
     void_rows: HashSet<usize>,
-    void_columns: HashSet<usize>
+    void_columns: HashSet<usize>,
+
+    // Number of void rows (or columns) before each index
+    void_rows_before: Vec<u64>,
+    void_columns_before: Vec<u64>
 }
 
 impl Space
@@ -24,7 +50,10 @@ impl Space
             matrix: matrix,
             galaxies_index: vec![],
             void_rows: HashSet::new(),
-            void_columns: HashSet::new()
+            void_columns: HashSet::new(),
+
+            void_rows_before: vec![],
+            void_columns_before: vec![]
         };
 
         space.build_indexes();
@@ -50,33 +79,190 @@ impl Space
         self.void_columns = (0..self.matrix[0].len())
             .filter(|j| self.matrix.iter().all(|row| row[*j].is_none()))
             .collect();
+
+        self.void_rows_before = prefix_count(self.matrix.len(), &self.void_rows);
+        self.void_columns_before = prefix_count(self.matrix[0].len(), &self.void_columns);
     }
 
-    pub fn find_galaxy_pairs(&self, expansion_factor: u64) -> Vec<((Coord, Coord), u64)>
+    // Coordinates once every void row and column is replaced by `expansion_factor` ones (0 removes them),
+    // None if they don't fit in a u64
+    pub fn expanded_coord(&self, coord: Coord, expansion_factor: u64) -> Option<(u64, u64)>
     {
-        let mut galaxy_pairs = vec![];
-        let mut already_processed: HashSet<(Coord, Coord)> = HashSet::new();
+        // Voids before are part of the coord, so removing them first never goes below 0
+        let expand = |coord: usize, voids: u64| (coord as u64 - voids).checked_add(expansion_factor.checked_mul(voids)?);
+
+        Some((
+            expand(coord.0, self.void_rows_before[coord.0])?,
+            expand(coord.1, self.void_columns_before[coord.1])?
+        ))
+    }
+
+    // Sum of the distances of every pair of galaxies
+    pub fn total_distance(&self, expansion_factor: u64) -> u64
+    {
+        match self.total_distance_with(&Expansion::Uniform(expansion_factor))
+        {
+            Some(total) => total,
+            None => panic!("Universe expanded {} times is too big", expansion_factor)
+        }
+    }
 
-        for a in &self.galaxies_index
+    // Galaxies in each row (or column)
+    fn galaxies_per_line(&self, rows: bool) -> Vec<u64>
+    {
+        let len = if rows { self.matrix.len() } else { self.matrix[0].len() };
+        let mut counts = vec![0; len];
+
+        for g in &self.galaxies_index
         {
-            for b in self.galaxies_index.iter().skip(1).filter(|b| *b != a)
+            counts[if rows { g.0 } else { g.1 }] += 1;
+        }
+
+        counts
+    }
+
+    // Number of rows (or columns) each original row (or column) takes once expanded, None if it doesn't fit in a u64
+    // Each block is the original line, then `size - 1` empty lines added by previous passes
+    fn expanded_sizes(&self, expansion: &Expansion, sizes: (Vec<u64>, Vec<u64>)) -> Option<(Vec<u64>, Vec<u64>)>
+    {
+        if let Expansion::Repeated(model, n) = expansion
+        {
+            return (0..*n).try_fold(sizes, |sizes, _| self.expanded_sizes(model, sizes));
+        }
+
+        // Size of a line with `galaxies` galaxies, when lines across are `width` long
+        let line_size = |rows: bool, galaxies: u64, width: u64| -> Option<u64>
+        {
+            let uniform = |factor: u64| if galaxies == 0 { factor } else { 1 };
+
+            match expansion
             {
-                if !already_processed.contains(&(*a, *b)) && !already_processed.contains(&(*b, *a))
+                Expansion::Uniform(factor) => Some(uniform(*factor)),
+                Expansion::Axes { rows: rows_factor, columns: columns_factor } =>
+                    Some(uniform(if rows { *rows_factor } else { *columns_factor })),
+                Expansion::Density(factor) =>
                 {
-                    let distance = distance(&self, (*a, *b), expansion_factor);
+                    if width == 0 { return Some(0) }
+
+                    // Factor 0 shrinks the empty lines, signed to not underflow
+                    let empty = (width - galaxies) as i128;
+                    (1 + (*factor as i128 - 1) * empty / width as i128).try_into().ok()
+                },
+                Expansion::Repeated(_, _) => panic!("Repeated expansions are applied pass by pass")
+            }
+        };
+
+        let (row_sizes, column_sizes) = sizes;
 
-                    galaxy_pairs.push(
-                        ((*a, *b), distance)
-                    );
+        let height = row_sizes.iter().try_fold(0u64, |acc, s| acc.checked_add(*s))?;
+        let width = column_sizes.iter().try_fold(0u64, |acc, s| acc.checked_add(*s))?;
 
-                    // Insert permutations and revers to get only unique perms
-                    already_processed.insert((*a, *b));
-                    already_processed.insert((*b, *a));
-                }
+        let grow = |rows: bool, sizes: &Vec<u64>, width: u64| -> Option<Vec<u64>>
+        {
+            self.galaxies_per_line(rows)
+                .iter()
+                .zip(sizes)
+                .map(|(galaxies, size)| {
+                    // Removed by a previous pass
+                    if *size == 0 { return Some(0) }
+
+                    let empty_lines = (size - 1).checked_mul(line_size(rows, 0, width)?)?;
+                    line_size(rows, *galaxies, width)?.checked_add(empty_lines)
+                })
+                .collect()
+        };
+
+        Some((grow(true, &row_sizes, width)?, grow(false, &column_sizes, height)?))
+    }
+
+    // None if the expanded universe doesn't fit in u64 coordinates
+    pub fn expand_with(&self, expansion: &Expansion) -> Option<SparseUniverse>
+    {
+        let offsets = |sizes: &Vec<u64>| sizes
+            .iter()
+            .scan(0, |offset, size| {
+                let start = *offset;
+                *offset += size;
+
+                Some(start)
+            })
+            .collect::<Vec<u64>>();
+
+        let initial = (vec![1; self.matrix.len()], vec![1; self.matrix[0].len()]);
+        let (row_sizes, column_sizes) = self.expanded_sizes(expansion, initial)?;
+
+        let height = row_sizes.iter().try_fold(0u64, |acc, s| acc.checked_add(*s))?;
+        let width = column_sizes.iter().try_fold(0u64, |acc, s| acc.checked_add(*s))?;
+
+        // Galaxies stay on the first row / column of their expanded block
+        let row_offsets = offsets(&row_sizes);
+        let column_offsets = offsets(&column_sizes);
+
+        Some(SparseUniverse {
+            height,
+            width,
+            galaxies: self.galaxies_index
+                .iter()
+                .map(|g| (row_offsets[g.0], column_offsets[g.1]))
+                .collect()
+        })
+    }
+
+    // Each axis sorted then summed with prefix sums
+    pub fn total_distance_with(&self, expansion: &Expansion) -> Option<u64>
+    {
+        let (rows, columns): (Vec<u64>, Vec<u64>) = self.expand_with(expansion)?
+            .galaxies
+            .into_iter()
+            .unzip();
+
+        sum_of_differences(rows)?.checked_add(sum_of_differences(columns)?)
+    }
+
+    // Closest galaxy from any coordinate (ignoring a galaxy on the coordinate itself),
+    // None if there is no other galaxy or a distance doesn't fit in a u64
+    pub fn nearest_galaxy(&self, coord: Coord, expansion_factor: u64) -> Option<(Coord, u64)>
+    {
+        self.galaxies_index
+            .iter()
+            .filter(|g| **g != coord)
+            .map(|g| Some((*g, distance(self, (coord, *g), expansion_factor)?)))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min_by_key(|(_, d)| *d)
+    }
+
+    // The k pairs of galaxies the farthest apart, farthest first (None if a distance doesn't fit in a u64)
+    pub fn farthest_pairs(&self, k: usize, expansion_factor: u64) -> Option<Vec<((Coord, Coord), u64)>>
+    {
+        let mut galaxy_pairs = self.find_galaxy_pairs(expansion_factor)?;
+
+        if k < galaxy_pairs.len()
+        {
+            galaxy_pairs.select_nth_unstable_by(k, |a, b| b.1.cmp(&a.1));
+            galaxy_pairs.truncate(k);
+        }
+
+        galaxy_pairs.sort_by(|a, b| b.1.cmp(&a.1));
+
+        Some(galaxy_pairs)
+    }
+
+    pub fn find_galaxy_pairs(&self, expansion_factor: u64) -> Option<Vec<((Coord, Coord), u64)>>
+    {
+        let mut galaxy_pairs = vec![];
+
+        for (i, a) in self.galaxies_index.iter().enumerate()
+        {
+            for b in self.galaxies_index.iter().skip(i + 1)
+            {
+                galaxy_pairs.push(
+                    ((*a, *b), distance(self, (*a, *b), expansion_factor)?)
+                );
             }
         }
 
-        galaxy_pairs
+        Some(galaxy_pairs)
     }
 }
 
@@ -90,19 +276,7 @@ impl From<BufReader<File>> for Space
         {
             match line
             {
-                Ok(content) => {
-                    matrix.push(
-                        content.chars()
-                            .map(|c| {
-                                match c
-                                {
-                                    '#' => Some(Tile::Galaxy),
-                                    _ => None,
-                                }
-                            })
-                            .collect()
-                    );
-                },
+                Ok(content) => matrix.push(parse_line(&content)),
                 Err(e) => panic!("Error while reading file: {}", e)
             }
         }
@@ -111,43 +285,99 @@ impl From<BufReader<File>> for Space
     }
 }
 
-pub fn distance(space: &Space, (a, b): (Coord, Coord), expansion_factor: u64) -> u64
+impl From<&str> for Space
 {
-    let x_start = a.0.min(b.0);
-    let x_end   = a.0.max(b.0);
-    let y_start = a.1.min(b.1);
-    let y_end   = a.1.max(b.1);
-
-    let dx: u64 = (x_start..x_end).map(|i| {
-        if space.void_rows.contains(&i) { expansion_factor }
-        else { 1 }
-    })
-    .sum();
-
-    let dy: u64 = (y_start..y_end).map(|j| {
-        if space.void_columns.contains(&j) { expansion_factor }
-        else { 1 }
-    })
-    .sum();
-
-    return dx + dy;
+    fn from(value: &str) -> Self
+    {
+        Space::new(value.lines().map(parse_line).collect())
+    }
+}
+
+fn parse_line(content: &str) -> Vec<Option<Tile>>
+{
+    content.chars()
+        .map(|c| {
+            match c
+            {
+                '#' => Some(Tile::Galaxy),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+pub fn distance(space: &Space, (a, b): (Coord, Coord), expansion_factor: u64) -> Option<u64>
+{
+    let a = space.expanded_coord(a, expansion_factor)?;
+    let b = space.expanded_coord(b, expansion_factor)?;
+
+    return a.0.abs_diff(b.0).checked_add(a.1.abs_diff(b.1));
+}
+
+impl SparseUniverse
+{
+    pub fn render(&self) -> String
+    {
+        (0..self.height)
+            .map(|i| (0..self.width)
+                .map(|j| if self.galaxies.contains(&(i, j)) { '#' } else { '.' })
+                .collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn prefix_count(len: usize, indexes: &HashSet<usize>) -> Vec<u64>
+{
+    (0..len)
+        .scan(0, |count, i| {
+            let before = *count;
+            if indexes.contains(&i) { *count += 1 }
+
+            Some(before)
+        })
+        .collect()
+}
+
+// Sum of |a - b| for every pair of values, None if it doesn't fit in a u64
+fn sum_of_differences(mut values: Vec<u64>) -> Option<u64>
+{
+    values.sort_unstable();
+
+    // Each value is bigger than all the previous ones, so v * i never goes below the prefix
+    let (total, _) = values
+        .iter()
+        .enumerate()
+        .try_fold((0u64, 0u64), |(total, prefix), (i, v)| {
+            let differences = v.checked_mul(i as u64)? - prefix;
+
+            Some((total.checked_add(differences)?, prefix.checked_add(*v)?))
+        })?;
+
+    Some(total)
 }
 
 #[cfg(test)]
 mod tests
 {
-    use super::{Tile, Space};
+    use super::{Tile, Space, Expansion};
+
+    // Space read back from its rendered expansion
+    fn expanded(space: &Space, factor: u64) -> Space
+    {
+        Space::from(space.expand_with(&Expansion::Uniform(factor)).unwrap().render().as_str())
+    }
 
     #[test]
     fn test_expand_galaxy()
     {
-        let mut space = Space::new(vec![
+        let space = Space::new(vec![
             vec![Some(Tile::Galaxy), None, Some(Tile::Galaxy)],
             vec![None, None, None],
             vec![Some(Tile::Galaxy), None, Some(Tile::Galaxy)]
         ]);
 
-        space.expand(2);
+        let space = expanded(&space, 2);
 
         assert_eq!(space.matrix.len(), 4);
         assert_eq!(space.matrix[0].len(), 4);
@@ -157,11 +387,11 @@ mod tests
         assert!(space.matrix.iter().all(|c| c[1].is_none()));
         assert!(space.matrix.iter().all(|c| c[2].is_none()));
 
-        let mut space = Space::new(vec![
+        let space = Space::new(vec![
             vec![None, Some(Tile::Galaxy), None, Some(Tile::Galaxy), None],
         ]);
 
-        space.expand(2);
+        let space = expanded(&space, 2);
 
         assert_eq!(space.matrix.len(), 1);
         assert_eq!(space.matrix[0].len(), 8);
@@ -176,7 +406,7 @@ mod tests
         assert_eq!(space.matrix[0][7], None);
 
 
-        let mut space = Space::new(vec![
+        let space = Space::new(vec![
             vec![None],
             vec![Some(Tile::Galaxy)],
             vec![None],
@@ -184,7 +414,7 @@ mod tests
             vec![None],
         ]);
 
-        space.expand(2);
+        let space = expanded(&space, 2);
 
         assert_eq!(space.matrix.len(), 8);
         assert_eq!(space.matrix[0].len(), 1);
@@ -198,4 +428,122 @@ mod tests
         assert_eq!(space.matrix[6][0], None);
         assert_eq!(space.matrix[7][0], None);
     }
-}
\ No newline at end of file
+
+    const EXAMPLE: &str = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+
+    #[test]
+    fn test_total_distance()
+    {
+        let space = Space::from(EXAMPLE);
+
+        assert_eq!(space.total_distance(2), 374);
+        assert_eq!(space.total_distance(10), 1030);
+        assert_eq!(space.total_distance(100), 8410);
+
+        let pairs_total: u64 = space.find_galaxy_pairs(100).unwrap().iter().map(|p| p.1).sum();
+        assert_eq!(pairs_total, 8410);
+    }
+
+    #[test]
+    fn test_distance_overflow()
+    {
+        // Each axis fits, their sum doesn't
+        let space = Space::from("#..\n...\n..#");
+        let factor = u64::MAX / 2;
+
+        assert_eq!(space.expanded_coord((2, 2), factor), Some((factor + 1, factor + 1)));
+        assert_eq!(space.total_distance_with(&Expansion::Uniform(factor)), None);
+        assert_eq!(space.find_galaxy_pairs(factor), None);
+        assert_eq!(space.expanded_coord((2, 2), u64::MAX), None);
+
+        // Sum of the differences is too big even if every coordinate fits
+        let space = Space::from("#..\n...\n#..\n...\n#..");
+        assert_eq!(space.total_distance_with(&Expansion::Uniform(u64::MAX / 3)), None);
+    }
+
+    #[test]
+    fn test_small_factors()
+    {
+        let space = Space::from(EXAMPLE);
+
+        // 1 keeps the space as is, 0 removes void rows and columns
+        for (factor, expected) in [(1, 292), (0, 210)]
+        {
+            let pairs_total: u64 = space.find_galaxy_pairs(factor).unwrap().iter().map(|p| p.1).sum();
+
+            assert_eq!(space.total_distance(factor), expected);
+            assert_eq!(pairs_total, expected);
+        }
+
+        assert_eq!(space.expanded_coord((9, 4), 1), Some((9, 4)));
+        assert_eq!(space.expanded_coord((9, 4), 0), Some((7, 3)));
+    }
+
+    #[test]
+    fn test_queries()
+    {
+        let space = Space::from(EXAMPLE);
+
+        assert_eq!(space.nearest_galaxy((0, 3), 2).map(|n| n.1), Some(6));
+        assert_eq!(space.nearest_galaxy((9, 0), 2), Some(((9, 4), 5)));
+
+        let farthest = space.farthest_pairs(2, 2).unwrap();
+        assert_eq!(farthest.len(), 2);
+        assert_eq!(farthest[0], (((1, 7), (9, 0)), 19));
+        assert_eq!(farthest[1].1, 17);
+        assert!(space.find_galaxy_pairs(2).unwrap().iter().all(|p| p.1 <= farthest[1].1 || farthest.contains(p)));
+    }
+
+    #[test]
+    fn test_expansion_models()
+    {
+        let space = Space::from(EXAMPLE);
+
+        assert_eq!(space.total_distance_with(&Expansion::Uniform(10)), Some(1030));
+        assert_eq!(space.total_distance_with(&Expansion::Axes { rows: 10, columns: 10 }), Some(1030));
+        assert_eq!(space.total_distance_with(&Expansion::Repeated(Box::new(Expansion::Uniform(10)), 2)), Some(8410));
+
+        // Rows only
+        let universe = space.expand_with(&Expansion::Axes { rows: 3, columns: 1 }).unwrap();
+        assert_eq!((universe.height, universe.width), (14, 10));
+        assert!(universe.galaxies.contains(&(13, 4)));
+
+        // Full rows don't grow, empty ones grow by the whole factor
+        let space = Space::from("#.\n..");
+        let universe = space.expand_with(&Expansion::Density(5)).unwrap();
+        assert_eq!((universe.height, universe.width), (8, 8));
+        assert_eq!(universe.render().lines().next(), Some("#......."));
+
+        // Empty rows vanish, the others are kept
+        let universe = space.expand_with(&Expansion::Density(0)).unwrap();
+        assert_eq!((universe.height, universe.width), (1, 1));
+    }
+
+    #[test]
+    fn test_repeated_expansions()
+    {
+        let space = Space::from(EXAMPLE);
+
+        // Density is computed again on the universe grown by the previous pass
+        for model in [Expansion::Density(3), Expansion::Density(0), Expansion::Uniform(2), Expansion::Uniform(0), Expansion::Axes { rows: 2, columns: 3 }]
+        {
+            let once = Space::from(space.expand_with(&model).unwrap().render().as_str());
+            let twice = space.expand_with(&Expansion::Repeated(Box::new(model.clone()), 2)).unwrap();
+
+            assert_eq!(once.expand_with(&model).unwrap(), twice);
+        }
+
+        // Too big for u64 coordinates
+        assert_eq!(space.expand_with(&Expansion::Repeated(Box::new(Expansion::Uniform(1_000_000)), 10)), None);
+        assert_eq!(space.total_distance_with(&Expansion::Repeated(Box::new(Expansion::Density(1_000)), 8)), None);
+    }
+}
@@ -0,0 +1,303 @@
+use rand::Rng;
+
+use crate::State;
+
+// Number of arrangements for every suffix of the record:
+// ways[position][group] = arrangements of state[position..] matching groups[group..]
+pub struct ArrangementTable<'a>
+{
+    state: &'a [State],
+    groups: &'a [usize],
+
+    ways: Vec<Vec<u128>>,
+    // Number of operational springs before each position, to check a group fits in O(1)
+    operational_before: Vec<usize>
+}
+
+impl<'a> ArrangementTable<'a>
+{
+    pub fn new(state: &'a [State], groups: &'a [usize]) -> Self
+    {
+        let n = state.len();
+
+        let mut operational_before = vec![0; n + 1];
+        for (i, s) in state.iter().enumerate()
+        {
+            operational_before[i + 1] = operational_before[i] + (*s == State::Operational) as usize;
+        }
+
+        let mut table = ArrangementTable {
+            state,
+            groups,
+
+            ways: vec![vec![0; groups.len() + 1]; n + 1],
+            operational_before
+        };
+
+        // Nothing left to place in nothing left to fill
+        table.ways[n][groups.len()] = 1;
+
+        for position in (0..n).rev()
+        {
+            for group in 0..=groups.len()
+            {
+                let mut ways = 0;
+
+                if table.state[position] != State::Damaged
+                {
+                    ways += table.ways[position + 1][group];
+                }
+
+                if let Some(next) = table.group_end(position, group)
+                {
+                    ways += table.ways[next][group + 1];
+                }
+
+                table.ways[position][group] = ways;
+            }
+        }
+
+        table
+    }
+
+    pub fn count(&self) -> u128
+    {
+        self.ways[0][0]
+    }
+
+    // Lazily walk every valid arrangement, only following branches leading to a solution
+    pub fn iter(&self) -> ArrangementIter<'_, 'a>
+    {
+        let stack = if self.count() > 0 { vec![(0, 0, vec![])] } else { vec![] };
+
+        ArrangementIter { table: self, stack }
+    }
+
+    // Uniformly random arrangement, each branch is picked with a probability proportional to its count
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Option<Vec<State>>
+    {
+        if self.count() == 0 { return None }
+
+        let (mut position, mut group) = (0, 0);
+        let mut arrangement = vec![];
+
+        while position < self.state.len()
+        {
+            let operational_ways = self.operational_ways(position, group);
+            let pick = rng.gen_range(0..self.ways[position][group]);
+
+            if pick < operational_ways
+            {
+                arrangement.push(State::Operational);
+                position += 1;
+            }
+            else
+            {
+                let next = self.group_end(position, group).unwrap();
+
+                self.push_group(&mut arrangement, position, group, next);
+                (position, group) = (next, group + 1);
+            }
+        }
+
+        Some(arrangement)
+    }
+
+    // State every solution agrees on for each position, None if solutions disagree (or if there are none)
+    pub fn forced_positions(&self) -> Vec<Option<State>>
+    {
+        let n = self.state.len();
+        let total = self.count();
+
+        if total == 0 { return vec![None; n] }
+
+        // Arrangements of state[..position] with groups[..group] placed, the other way around than `ways`
+        let mut before = vec![vec![0u128; self.groups.len() + 1]; n + 1];
+        // Number of solutions where each position is damaged, as a difference array
+        let mut damaged = vec![0u128; n + 1];
+
+        before[0][0] = 1;
+
+        for position in 0..n
+        {
+            for group in 0..=self.groups.len()
+            {
+                let count = before[position][group];
+                if count == 0 { continue }
+
+                if self.state[position] != State::Damaged
+                {
+                    before[position + 1][group] += count;
+                }
+
+                if let Some(next) = self.group_end(position, group)
+                {
+                    before[next][group + 1] += count;
+
+                    let solutions = count * self.ways[next][group + 1];
+
+                    // Wrapping as the end may be subtracted before the start is added
+                    damaged[position] = damaged[position].wrapping_add(solutions);
+                    damaged[position + self.groups[group]] = damaged[position + self.groups[group]].wrapping_sub(solutions);
+                }
+            }
+        }
+
+        damaged
+            .iter()
+            .take(n)
+            .scan(0u128, |sum, d| {
+                *sum = sum.wrapping_add(*d);
+                Some(*sum)
+            })
+            .map(|d| match d
+            {
+                0 => Some(State::Operational),
+                d if d == total => Some(State::Damaged),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn operational_ways(&self, position: usize, group: usize) -> u128
+    {
+        if self.state[position] == State::Damaged { 0 }
+        else { self.ways[position + 1][group] }
+    }
+
+    fn push_group(&self, arrangement: &mut Vec<State>, position: usize, group: usize, next: usize)
+    {
+        let end = position + self.groups[group];
+
+        arrangement.extend((position..end).map(|_| State::Damaged));
+        arrangement.extend((end..next).map(|_| State::Operational));
+    }
+
+    // Where we continue if the group starts at this position (skipping its delimiter), None if it can't
+    pub fn group_end(&self, position: usize, group: usize) -> Option<usize>
+    {
+        let size = *self.groups.get(group)?;
+        let end = position + size;
+
+        if self.state[position] == State::Operational || end > self.state.len() { return None }
+
+        // Group must not contain operational springs
+        if self.operational_before[end] - self.operational_before[position] > 0 { return None }
+
+        // ...and must be followed by an operational one (or the end)
+        match self.state.get(end)
+        {
+            None => Some(end),
+            Some(State::Damaged) => None,
+            Some(_) => Some(end + 1)
+        }
+    }
+}
+
+pub struct ArrangementIter<'t, 'a>
+{
+    table: &'t ArrangementTable<'a>,
+    // Position, group and arrangement so far
+    stack: Vec<(usize, usize, Vec<State>)>
+}
+
+impl<'t, 'a> Iterator for ArrangementIter<'t, 'a>
+{
+    type Item = Vec<State>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let table = self.table;
+
+        while let Some((position, group, arrangement)) = self.stack.pop()
+        {
+            if position == table.state.len() { return Some(arrangement) }
+
+            // Pushed first so groups are placed as soon as possible first
+            if table.operational_ways(position, group) > 0
+            {
+                let mut operational = arrangement.clone();
+                operational.push(State::Operational);
+
+                self.stack.push((position + 1, group, operational));
+            }
+
+            if let Some(next) = table.group_end(position, group)
+            {
+                if table.ways[next][group + 1] > 0
+                {
+                    let mut damaged = arrangement;
+                    table.push_group(&mut damaged, position, group, next);
+
+                    self.stack.push((next, group + 1, damaged));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+pub fn count_arrangements(state: &[State], groups: &[usize]) -> u128
+{
+    ArrangementTable::new(state, groups).count()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{parse_line, State};
+    use super::ArrangementTable;
+
+    fn as_string(states: &[State]) -> String
+    {
+        states.iter().map(|s| if *s == State::Damaged { '#' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn test_iter()
+    {
+        let record = parse_line("?###???????? 3,2,1");
+        let table = ArrangementTable::new(&record.state, &record.damaged_groups);
+
+        let arrangements: Vec<String> = table.iter().map(|a| as_string(&a)).collect();
+
+        assert_eq!(arrangements.len(), 10);
+        assert_eq!(arrangements[0], ".###.##.#...");
+        assert_eq!(arrangements[9], ".###....##.#");
+
+        let record = parse_line("### 1");
+        assert_eq!(ArrangementTable::new(&record.state, &record.damaged_groups).iter().count(), 0);
+    }
+
+    #[test]
+    fn test_sample()
+    {
+        let record = parse_line(".??..??...?##. 1,1,3");
+        let table = ArrangementTable::new(&record.state, &record.damaged_groups);
+
+        let all: Vec<Vec<State>> = table.iter().collect();
+        let mut rng = StdRng::seed_from_u64(12);
+
+        for _ in 0..50
+        {
+            assert!(all.contains(&table.sample(&mut rng).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_forced_positions()
+    {
+        let record = parse_line("?###???????? 3,2,1");
+        let table = ArrangementTable::new(&record.state, &record.damaged_groups);
+
+        let forced = table.forced_positions();
+
+        assert_eq!(forced[0], Some(State::Operational));
+        assert_eq!(&forced[1..4], &[Some(State::Damaged); 3]);
+        assert_eq!(forced[4], Some(State::Operational));
+        assert!(forced[5..].iter().all(|f| f.is_none()));
+    }
+}
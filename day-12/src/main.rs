@@ -1,7 +1,14 @@
-use std::{io::{BufReader, BufRead}, fs::File, collections::HashMap};
+use std::{io::{BufReader, BufRead}, fs::File};
+
+mod arrangements;
+
+use arrangements::{count_arrangements, ArrangementTable};
+
+// Switch to true to print counts, forced springs and a random arrangement for every record
+const DEBUG: bool = false;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
-enum State
+pub enum State
 {
     Operational,
     Damaged,
@@ -22,14 +29,47 @@ fn main()
 
     let records = parse(buffer);
 
-    let mut cache = HashMap::new();
+    if DEBUG
+    {
+        let mut rng = rand::thread_rng();
+
+        for record in &records
+        {
+            let table = ArrangementTable::new(&record.state, &record.damaged_groups);
+
+            let forced: String = table.forced_positions()
+                .iter()
+                .map(|s| match s
+                {
+                    Some(state) => state_char(state),
+                    None => '?'
+                })
+                .collect();
+
+            let sample: String = table.sample(&mut rng)
+                .unwrap_or_default()
+                .iter()
+                .map(state_char)
+                .collect();
+
+            let first: String = table.iter()
+                .next()
+                .unwrap_or_default()
+                .iter()
+                .map(state_char)
+                .collect();
+
+            println!("{} {:?}: {} arrangements, forced {}, first {}, sample {}",
+                record.state.iter().map(state_char).collect::<String>(), record.damaged_groups, table.count(), forced, first, sample);
+        }
+    }
 
-    let result: usize = records.iter().map(|r| possible_solutions(&r.state, &r.damaged_groups, &mut cache)).sum();
+    let result: u128 = records.iter().map(|r| count_arrangements(&r.state, &r.damaged_groups)).sum();
     println!("Result: {}", result);
 
-    let result2: usize = records.iter()
+    let result2: u128 = records.iter()
         .map(|r| expand_record(&r, 5))
-        .map(|r| possible_solutions(&r.state, &r.damaged_groups, &mut cache))
+        .map(|r| count_arrangements(&r.state, &r.damaged_groups))
         .sum();
 
     println!("Result 2: {}", result2);
@@ -69,8 +109,20 @@ fn parse_line(content: &str) -> Record
     Record { state, damaged_groups }
 }
 
+fn state_char(state: &State) -> char
+{
+    match state
+    {
+        State::Operational => '.',
+        State::Damaged => '#',
+        State::Unknown => '?'
+    }
+}
+
 fn expand_record(record: &Record, times: usize) -> Record
 {
+    if times == 0 { return Record { state: vec![], damaged_groups: vec![] } }
+
     let mut state = record.state.clone();
     let damaged_groups = record.damaged_groups.clone().repeat(times);
 
@@ -83,99 +135,19 @@ fn expand_record(record: &Record, times: usize) -> Record
     Record { state, damaged_groups }
 }
 
-fn possible_solutions(state: &Vec<State>, groups: &Vec<usize>, mut cache: &mut HashMap<(Vec<State>, Vec<usize>), usize>) -> usize
-{
-    if let Some(result) = cache.get(&(state.clone(), groups.clone())) { return *result; }
-
-    match state.first()
-    {
-        Some(State::Operational) => {
-            let remaining: Vec<_> = state.iter().skip_while(|s| **s == State::Operational).map(|s| *s).collect();
-
-            // If remaining is smaller than next group, impossible case
-            if remaining.len() < *groups.first().unwrap_or(&0)
-            {
-                cache.insert((state.clone(), groups.clone()), 0);
-                return 0;
-            }
-
-            return possible_solutions(&remaining, &groups, &mut cache);
-        },
-        Some(State::Damaged) => {
-            match groups.first()
-            {
-                Some(size) => {
-                    let matched_group: Vec<_> = state.iter().take(*size).collect();
-
-                    if matched_group.len() == *size && matched_group.iter().all(|s| **s != State::Operational)
-                    {
-                        // If next is a unknown, we need to explicitely set it to a Operational (to have a delimiter)
-                        let mut remaining : Vec<_> = state.iter().skip(*size).map(|s| *s).collect();
-                        let remaining_groups = groups.iter().skip(1).map(|i| *i).collect();
-
-                        match remaining.first_mut()
-                        {
-                            // We have another damaged after, so we didn't match the group
-                            Some(State::Damaged) => {
-                                cache.insert((state.clone(), groups.clone()), 0);
-                                return 0;
-                            }
-                            // If the next elem is unknown we can set it to operational (as if we have damaged it doesn't work)
-                            Some(first) if *first == State::Unknown => *first = State::Operational,
-                            _ => ()
-                        }
-
-                        return possible_solutions(&remaining, &remaining_groups, &mut cache);
-                    }
-                    else
-                    {
-                        cache.insert((state.clone(), groups.clone()), 0);
-                        return 0;
-                    }
-                }
-                None => {
-                    cache.insert((state.clone(), groups.clone()), 0);
-                    return 0
-                }
-            }
-        },
-        Some(State::Unknown) => {
-            let mut state_operational = state.clone();
-            state_operational[0] = State::Operational;
-
-            let mut state_damaged = state.clone();
-            state_damaged[0] = State::Damaged;
-
-            let result = possible_solutions(&state_operational, &groups, &mut cache) +
-                                possible_solutions(&state_damaged, &groups, &mut cache);
-
-            cache.insert((state.clone(), groups.clone()), result);
-
-            return result;
-        },
-        None => {
-            let result = if groups.is_empty() {  1 }
-                else { return 0 };
-
-            cache.insert((state.clone(), groups.clone()), result);
-            return result;
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests
 {
-    use std::{collections::HashMap, io::BufReader, fs::File};
+    use std::{io::BufReader, fs::File};
 
-    use crate::{Record, State, possible_solutions, parse, parse_line, expand_record};
+    use crate::{Record, State, count_arrangements, parse, parse_line, expand_record};
 
     #[test]
     fn test_possible_solutions()
     {
         let record = Record { state: vec![State::Operational, State::Unknown, State::Operational], damaged_groups: vec![1] };
 
-        assert_eq!(possible_solutions(&record.state, &record.damaged_groups, &mut HashMap::new()), 1);
+        assert_eq!(count_arrangements(&record.state, &record.damaged_groups), 1);
 
         let record = Record { state: vec![
             State::Operational,
@@ -184,7 +156,7 @@ mod tests
             State::Unknown,
             State::Operational], damaged_groups: vec![1] };
 
-        assert_eq!(possible_solutions(&record.state, &record.damaged_groups, &mut HashMap::new()), 3);
+        assert_eq!(count_arrangements(&record.state, &record.damaged_groups), 3);
     }
 
     #[test]
@@ -192,10 +164,10 @@ mod tests
     {
         // Expected: 5
         let record = parse_line("?.?#.?###??.#???? 2,4,1,2");
-        assert_eq!(possible_solutions(&record.state, &record.damaged_groups, &mut HashMap::new()), 5);
+        assert_eq!(count_arrangements(&record.state, &record.damaged_groups), 5);
 
         let record = parse_line(".??#?????.???????# 4,5,2");
-        assert_eq!(possible_solutions(&record.state, &record.damaged_groups, &mut HashMap::new()), 3);
+        assert_eq!(count_arrangements(&record.state, &record.damaged_groups), 3);
     }
 
     #[test]
@@ -204,7 +176,7 @@ mod tests
         let records = parse(BufReader::new(File::open("./input2.txt").unwrap()));
 
         let solutions: Vec<_> = records.iter()
-            .map(|r| possible_solutions(&r.state, &r.damaged_groups, &mut HashMap::new()))
+            .map(|r| count_arrangements(&r.state, &r.damaged_groups))
             .collect();
 
         assert_eq!(solutions, vec![
@@ -217,13 +189,11 @@ mod tests
     {
         let records = parse(BufReader::new(File::open("./input.txt").unwrap()));
 
-        let mut cache = HashMap::new();
-
         let solutions: Vec<_> = records.iter()
-            .map(|r| possible_solutions(&r.state, &r.damaged_groups, &mut cache))
+            .map(|r| count_arrangements(&r.state, &r.damaged_groups))
             .collect();
 
-        assert_eq!(solutions.iter().sum::<usize>(), 7361);
+        assert_eq!(solutions.iter().sum::<u128>(), 7361);
     }
 
     #[test]
@@ -236,4 +206,22 @@ mod tests
         assert_eq!(expanded_record.state.len(), 9);
         assert_eq!(expanded_record.damaged_groups.len(), 5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_unfolded_records()
+    {
+        let unfolded = |line: &str, times: usize| {
+            let record = expand_record(&parse_line(line), times);
+            count_arrangements(&record.state, &record.damaged_groups)
+        };
+
+        assert_eq!(unfolded("???.### 1,1,3", 5), 1);
+        assert_eq!(unfolded(".??..??...?##. 1,1,3", 5), 16384);
+        assert_eq!(unfolded("?###???????? 3,2,1", 5), 506250);
+        assert_eq!(unfolded("?###???????? 3,2,1", 1), 10);
+        assert_eq!(unfolded("?###???????? 3,2,1", 0), 1);
+
+        // Doesn't fit in a u64 anymore
+        assert!(unfolded("?###???????? 3,2,1", 30) > u64::MAX as u128);
+    }
+}
@@ -1,7 +1,20 @@
 use std::fs;
 
 mod pattern;
-use pattern::Pattern;
+use pattern::{Pattern, Mirror, Axis};
+
+// Switch to true to list the patterns without exactly one reflection
+const DEBUG: bool = false;
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        if $crate::DEBUG
+        {
+            print!("[*] ");
+            println!($($arg)*);
+        }
+    }};
+}
 
 fn main()
 {
@@ -10,6 +23,20 @@ fn main()
     let result = summarize(&patterns, 0);
     let result2 = summarize(&patterns, 1);
 
+    for error_count in 0..=1
+    {
+        for (i, pattern) in patterns.iter().enumerate()
+        {
+            match pattern.mirror_with_errors(error_count)
+            {
+                Mirror::None => debug!("Pattern {} has no reflection with {} error(s)", i, error_count),
+                Mirror::Multiple(r) => debug!("Pattern {} has {} reflections with {} error(s): {:?}", i, r.len(), error_count, r),
+                Mirror::Single(r) if error_count == 1 => debug!("Pattern {} has its smudge at {:?}", i, r.smudge()),
+                Mirror::Single(_) => ()
+            }
+        }
+    }
+
     println!("Result: {}", result);
     println!("Result 2: {}", result2);
 }
@@ -21,33 +48,23 @@ fn parse(file_content: &str) -> Vec<Pattern>
         .collect()
 }
 
+// First vertical and first horizontal reflection of each pattern, the ones without any count for nothing
 fn summarize(patterns: &Vec<Pattern>, error_count: usize) -> usize
 {
-    let vertical_reflections: Vec<_> = patterns.iter()
-        .map(|p| p.vertical_reflection_with_errors(error_count))
-        .collect();
-
-    let n_v_reflections: usize = vertical_reflections.iter()
-        .filter(|r| r.is_some())
-        .map(|r| r.unwrap().0 + 1)
-        .sum();
-
-    let horizontal_reflections: Vec<_> = patterns.iter()
-        .map(|p| p.horizontal_reflection_with_errors(error_count))
-        .collect();
-
-    let n_h_reflections: usize = horizontal_reflections.iter()
-        .filter(|r| r.is_some())
-        .map(|r| r.unwrap().0 + 1)
-        .sum();
-
-    n_v_reflections + 100 * n_h_reflections
+    patterns.iter()
+        .flat_map(|p| {
+            let reflections = p.reflections_with_errors(error_count);
+
+            [Axis::Vertical, Axis::Horizontal].map(|axis| reflections.iter().find(|r| r.axis == axis).map(|r| r.summary()))
+        })
+        .flatten()
+        .sum()
 }
 
 #[cfg(test)]
 mod tests
 {
-    use crate::{parse, summarize};
+    use crate::{parse, summarize, pattern::{Pattern, Mirror, Axis}};
 
     #[test]
     fn text_example1()
@@ -95,4 +112,73 @@ mod tests
 
         assert_eq!(summarize(&patterns, 1), 400);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_smudge()
+    {
+        let pattern = Pattern::from(
+"#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.");
+
+        let reflection = match pattern.mirror_with_errors(1)
+        {
+            Mirror::Single(reflection) => reflection,
+            m => panic!("Expected a single reflection, got {:?}", m)
+        };
+
+        assert_eq!(reflection.axis, Axis::Horizontal);
+        assert_eq!(reflection.between, (2, 3));
+        assert_eq!(reflection.smudge(), Some((0, 0)));
+        assert_eq!(reflection.mismatches, vec![((0, 0), (5, 0))]);
+
+        // Perfect one is vertical, smudge makes it horizontal
+        assert_eq!(pattern.reflections().iter().filter(|r| r.mismatches.is_empty()).count(), 1);
+    }
+
+    #[test]
+    fn test_multiple_or_no_reflection()
+    {
+        let symmetric = Pattern::from(
+"#..#
+.##.
+.##.
+#..#");
+
+        match symmetric.mirror_with_errors(0)
+        {
+            Mirror::Multiple(reflections) =>
+            {
+                assert_eq!(reflections.len(), 2);
+                assert!(reflections.iter().any(|r| r.axis == Axis::Vertical));
+                assert!(reflections.iter().any(|r| r.axis == Axis::Horizontal));
+            },
+            m => panic!("Expected multiple reflections, got {:?}", m)
+        }
+
+        let asymmetric = Pattern::from(
+"#..
+..#
+.#.");
+
+        assert_eq!(asymmetric.mirror_with_errors(0), Mirror::None);
+    }
+
+    #[test]
+    fn test_summarize_first_of_each_axis()
+    {
+        // Mirrors between columns 1 and 2, 3 and 4, and between rows 1 and 2
+        let patterns = parse(
+"#..##.
+.##..#
+.##..#
+#..##.");
+
+        assert_eq!(patterns[0].reflections_with_errors(0).len(), 3);
+        assert_eq!(summarize(&patterns, 0), 2 + 200);
+    }
+}
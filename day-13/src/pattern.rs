@@ -5,6 +5,34 @@ pub enum Tile
     Rock
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Axis
+{
+    // Mirror between two columns
+    Vertical,
+    // Mirror between two rows
+    Horizontal
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Reflection
+{
+    pub axis: Axis,
+    // Lines (columns or rows) on each side of the mirror
+    pub between: (usize, usize),
+    // (row, col) of each mismatched cell and of its mirrored cell
+    pub mismatches: Vec<((usize, usize), (usize, usize))>
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Mirror
+{
+    None,
+    Single(Reflection),
+    // Both a vertical and an horizontal one, or several on the same axis
+    Multiple(Vec<Reflection>)
+}
+
 pub struct Pattern
 {
     matrix: Vec<Vec<Tile>>
@@ -23,74 +51,92 @@ impl Pattern
         self.matrix.len()
     }
 
-    pub fn row(&self, index: usize) -> Vec<&Tile>
+    // Every possible mirror position, whatever its number of mismatches
+    pub fn reflections(&self) -> Vec<Reflection>
     {
-        self.matrix[index].iter()
-            .map(|t| t)
-            .collect()
+        let vertical = (1..self.width())
+            .map(|j| self.reflection(Axis::Vertical, (j - 1, j)));
+
+        let horizontal = (1..self.height())
+            .map(|i| self.reflection(Axis::Horizontal, (i - 1, i)));
+
+        vertical.chain(horizontal).collect()
     }
 
-    pub fn col(&self, index: usize) -> Vec<&Tile>
+    pub fn reflections_with_errors(&self, error_count: usize) -> Vec<Reflection>
     {
-        self.matrix.iter()
-            .map(|row| &row[index])
+        self.reflections()
+            .into_iter()
+            .filter(|r| r.mismatches.len() == error_count)
             .collect()
     }
 
-    pub fn vertical_reflection_with_errors(&self, error_count: usize) -> Option<(usize, usize)>
+    pub fn mirror_with_errors(&self, error_count: usize) -> Mirror
     {
-        for j in 1..self.width()
-        {
-            let n_diffs = self.vertical_reflection_diffs(((j - 1), j));
+        let mut reflections = self.reflections_with_errors(error_count);
 
-            if n_diffs == error_count
-            {
-                return Some((j - 1, j))
-            }
+        match reflections.len()
+        {
+            0 => Mirror::None,
+            1 => Mirror::Single(reflections.remove(0)),
+            _ => Mirror::Multiple(reflections)
         }
-
-        None
     }
 
-    fn vertical_reflection_diffs(&self, coord: (usize, usize)) -> usize
+    fn reflection(&self, axis: Axis, between: (usize, usize)) -> Reflection
     {
-        let iter = (0..=coord.0).rev().zip(coord.1..self.width());
-        let mut n_diffs = 0;
+        let (lines, line_len) = match axis
+        {
+            Axis::Vertical => (self.width(), self.height()),
+            Axis::Horizontal => (self.height(), self.width())
+        };
 
-        for (a, b) in iter
+        // (row, col) of the k-th cell of a line
+        let cell = |line: usize, k: usize| match axis
         {
-            n_diffs += diffs(&self.col(a), &self.col(b));
-        }
+            Axis::Vertical => (k, line),
+            Axis::Horizontal => (line, k)
+        };
 
-        return n_diffs;
-    }
+        let mut mismatches = vec![];
 
-    pub fn horizontal_reflection_with_errors(&self, error_count: usize) -> Option<(usize, usize)>
-    {
-        for i in 1..self.height()
+        for (a, b) in (0..=between.0).rev().zip(between.1..lines)
         {
-            let n_diffs = self.horizontal_reflection_diffs(((i - 1), i));
-
-            if n_diffs == error_count
+            for k in 0..line_len
             {
-                return Some((i - 1, i))
+                let (cell_a, cell_b) = (cell(a, k), cell(b, k));
+
+                if self.matrix[cell_a.0][cell_a.1] != self.matrix[cell_b.0][cell_b.1]
+                {
+                    mismatches.push((cell_a, cell_b));
+                }
             }
         }
 
-        None
+        Reflection { axis, between, mismatches }
     }
+}
 
-    fn horizontal_reflection_diffs(&self, coord: (usize, usize)) -> usize
+impl Reflection
+{
+    // Columns on the left, or 100 times the rows above
+    pub fn summary(&self) -> usize
     {
-        let iter = (0..=coord.0).rev().zip(coord.1..self.height());
-        let mut n_diffs = 0;
-
-        for (a, b) in iter
+        match self.axis
         {
-            n_diffs += diffs(&self.row(a), &self.row(b));
+            Axis::Vertical => self.between.0 + 1,
+            Axis::Horizontal => 100 * (self.between.0 + 1)
         }
+    }
 
-        return n_diffs;
+    // Cell to flip to make this reflection perfect, if there is exactly one
+    pub fn smudge(&self) -> Option<(usize, usize)>
+    {
+        match self.mismatches.as_slice()
+        {
+            [(cell, _)] => Some(*cell),
+            _ => None
+        }
     }
 }
 
@@ -114,15 +160,3 @@ impl From<&str> for Pattern
         Pattern { matrix: tiles }
     }
 }
-
-fn diffs(a: &Vec<&Tile>, b: &Vec<&Tile>) -> usize
-{
-    if a.len() != b.len() { panic!("Vec must have the same size.") }
-
-    a.iter().enumerate()
-        .map(|(i, t)| {
-            if b[i] != *t { 1 }
-            else { 0 }
-        })
-        .sum::<usize>()
-}
\ No newline at end of file
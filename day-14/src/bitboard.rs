@@ -0,0 +1,448 @@
+use std::borrow::Cow;
+
+use cycle::{Simulation, Strategy};
+
+use crate::{platform::Direction, program::SPIN_CYCLE};
+
+const WORD: usize = 64;
+
+// Platform packed as bits, one bit per tile, each line padded to a whole number of words
+// Round rocks are packed along rows or columns depending on the tilt, only the layout used by the last tilt is up to date
+#[derive(Debug, Clone)]
+pub struct BitPlatform
+{
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words_per_column: usize,
+
+    round_rocks: Vec<u64>,
+    // Same rocks, column by column
+    round_rocks_by_column: Vec<u64>,
+    // True when round_rocks_by_column is the up to date layout
+    by_column: bool,
+    cube_rocks: Vec<u64>,
+
+    // Ranges of each row (and column) between cube rocks, where round rocks can roll
+    row_segments: Vec<Vec<(usize, usize)>>,
+    column_segments: Vec<Vec<(usize, usize)>>
+}
+
+impl BitPlatform
+{
+    pub fn tilt(&mut self, direction: Direction)
+    {
+        match direction
+        {
+            Direction::North | Direction::South =>
+            {
+                self.switch_layout(true);
+                pack(&mut self.round_rocks_by_column, self.words_per_column, &self.column_segments, direction == Direction::North);
+            },
+            Direction::West | Direction::East =>
+            {
+                self.switch_layout(false);
+                pack(&mut self.round_rocks, self.words_per_row, &self.row_segments, direction == Direction::West);
+            }
+        }
+    }
+
+    // Rebuilds the layout the next tilt packs along, from the up to date one
+    fn switch_layout(&mut self, by_column: bool)
+    {
+        if self.by_column == by_column { return }
+
+        if by_column
+        {
+            self.round_rocks_by_column = transpose(&self.round_rocks, self.words_per_row, self.width, self.words_per_column);
+        }
+        else
+        {
+            self.round_rocks = transpose(&self.round_rocks_by_column, self.words_per_column, self.height, self.words_per_row);
+        }
+
+        self.by_column = by_column;
+    }
+
+    // Round rocks row by row, only transposed when the last tilt was along columns
+    fn rows(&self) -> Cow<'_, [u64]>
+    {
+        if self.by_column
+        {
+            Cow::Owned(transpose(&self.round_rocks_by_column, self.words_per_column, self.height, self.words_per_row))
+        }
+        else
+        {
+            Cow::Borrowed(&self.round_rocks)
+        }
+    }
+
+    pub fn tilt_cycle(&mut self)
+    {
+        for direction in SPIN_CYCLE
+        {
+            self.tilt(direction)
+        }
+    }
+
+    pub fn run_cycle(&mut self, cycles: usize)
+    {
+        cycle::fast_forward(self, cycles, Strategy::Brent);
+    }
+
+    pub fn load(&self) -> u64
+    {
+        let rows = self.rows();
+
+        (0..self.height)
+            .map(|r| {
+                let n_round_rocks: u32 = rows[r * self.words_per_row..(r + 1) * self.words_per_row]
+                    .iter()
+                    .map(|w| w.count_ones())
+                    .sum();
+
+                (self.height - r) as u64 * n_round_rocks as u64
+            })
+            .sum()
+    }
+}
+
+// In each segment between cube rocks, count the rocks and pack them at the start or at the end of it
+fn pack(bits: &mut [u64], words_per_line: usize, segments: &[Vec<(usize, usize)>], to_start: bool)
+{
+    for (line, line_segments) in segments.iter().enumerate()
+    {
+        let offset = line * words_per_line;
+
+        for (start, end) in line_segments
+        {
+            let count = count_range(bits, offset, *start, *end);
+
+            set_range(bits, offset, *start, *end, false);
+
+            if to_start { set_range(bits, offset, *start, start + count, true) }
+            else { set_range(bits, offset, end - count, *end, true) }
+        }
+    }
+}
+
+// Rows become columns (`length` of them), 64x64 blocks at a time
+fn transpose(bits: &[u64], words_per_line: usize, length: usize, words_per_out_line: usize) -> Vec<u64>
+{
+    let lines = bits.len() / words_per_line;
+    let mut transposed = vec![0; length * words_per_out_line];
+
+    for line_block in 0..words_per_out_line
+    {
+        for w in 0..words_per_line
+        {
+            // Lines past the end are empty
+            let mut block = [0; WORD];
+
+            for (i, word) in block.iter_mut().enumerate()
+            {
+                let line = line_block * WORD + i;
+                if line < lines { *word = bits[line * words_per_line + w] }
+            }
+
+            transpose_block(&mut block);
+
+            // Padding bits of the lines are always 0, so are the out lines past `length`
+            for (i, word) in block.iter().enumerate()
+            {
+                let out_line = w * WORD + i;
+                if out_line < length { transposed[out_line * words_per_out_line + line_block] = *word }
+            }
+        }
+    }
+
+    transposed
+}
+
+// Bit `j` of word `i` goes to bit `i` of word `j`: swaps the off-diagonal halves, then quarters, and so on
+fn transpose_block(block: &mut [u64; WORD])
+{
+    let mut j = WORD / 2;
+    let mut mask: u64 = 0x0000_0000_FFFF_FFFF;
+
+    while j != 0
+    {
+        let mut k = 0;
+
+        while k < WORD
+        {
+            let t = ((block[k] >> j) ^ block[k + j]) & mask;
+
+            block[k] ^= t << j;
+            block[k + j] ^= t;
+
+            // Next word without bit `j`, its pair is `k + j`
+            k = (k + j + 1) & !j;
+        }
+
+        j /= 2;
+        mask ^= mask << j;
+    }
+}
+
+// Ranges between cube rocks of each line
+fn segments(cube_rocks: &[u64], words_per_line: usize, length: usize) -> Vec<Vec<(usize, usize)>>
+{
+    (0..cube_rocks.len() / words_per_line)
+        .map(|line| {
+            let mut segments = vec![];
+            let mut start = 0;
+
+            for c in 0..=length
+            {
+                if c == length || get(cube_rocks, line * words_per_line, c)
+                {
+                    if start < c { segments.push((start, c)) }
+                    start = c + 1;
+                }
+            }
+
+            segments
+        })
+        .collect()
+}
+
+fn get(bits: &[u64], offset: usize, col: usize) -> bool
+{
+    bits[offset + col / WORD] >> (col % WORD) & 1 == 1
+}
+
+// Bits [start, end) of the word at `index`, as a mask
+fn word_mask(index: usize, start: usize, end: usize) -> u64
+{
+    let word_start = index * WORD;
+    let from = start.max(word_start) - word_start;
+    let to = end.min(word_start + WORD) - word_start;
+
+    if from >= to { return 0 }
+
+    let high = if to == WORD { u64::MAX } else { (1 << to) - 1 };
+
+    high & !((1 << from) - 1)
+}
+
+fn count_range(bits: &[u64], offset: usize, start: usize, end: usize) -> usize
+{
+    if start >= end { return 0 }
+
+    (start / WORD..=(end - 1) / WORD)
+        .map(|w| (bits[offset + w] & word_mask(w, start, end)).count_ones() as usize)
+        .sum()
+}
+
+fn set_range(bits: &mut [u64], offset: usize, start: usize, end: usize, value: bool)
+{
+    if start >= end { return }
+
+    for w in start / WORD..=(end - 1) / WORD
+    {
+        let mask = word_mask(w, start, end);
+
+        if value { bits[offset + w] |= mask }
+        else { bits[offset + w] &= !mask }
+    }
+}
+
+// One step is a full tilt cycle
+impl Simulation for BitPlatform
+{
+    // Only round rocks move, they are enough to identify a state
+    type Fingerprint = Vec<u64>;
+
+    fn step(&mut self)
+    {
+        self.tilt_cycle();
+    }
+
+    fn fingerprint(&self) -> Self::Fingerprint
+    {
+        self.rows().into_owned()
+    }
+}
+
+// Same rocks, whichever layout is up to date
+impl PartialEq for BitPlatform
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.width == other.width && self.height == other.height &&
+            self.cube_rocks == other.cube_rocks && self.rows() == other.rows()
+    }
+}
+
+impl Eq for BitPlatform {}
+
+impl From<&str> for BitPlatform
+{
+    fn from(value: &str) -> Self
+    {
+        let rows: Vec<&str> = value.split('\n').collect();
+
+        let height = rows.len();
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let words_per_row = width.div_ceil(WORD).max(1);
+
+        let mut round_rocks = vec![0; height * words_per_row];
+        let mut cube_rocks = vec![0; height * words_per_row];
+
+        for (r, row) in rows.iter().enumerate()
+        {
+            for (c, tile) in row.chars().enumerate()
+            {
+                let bit = 1 << (c % WORD);
+
+                match tile
+                {
+                    'O' => round_rocks[r * words_per_row + c / WORD] |= bit,
+                    '#' => cube_rocks[r * words_per_row + c / WORD] |= bit,
+                    _ => ()
+                }
+            }
+        }
+
+        let words_per_column = height.div_ceil(WORD).max(1);
+        let cube_rocks_by_column = transpose(&cube_rocks, words_per_row, width, words_per_column);
+
+        BitPlatform {
+            width,
+            height,
+            words_per_row,
+            words_per_column,
+
+            round_rocks_by_column: vec![0; width * words_per_column],
+            round_rocks,
+            by_column: false,
+
+            row_segments: segments(&cube_rocks, words_per_row, width),
+            column_segments: segments(&cube_rocks_by_column, words_per_column, height),
+            cube_rocks
+        }
+    }
+}
+
+impl std::fmt::Display for BitPlatform
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        // Transposed once for the whole platform
+        let round_rocks = self.rows();
+
+        let rows: Vec<String> = (0..self.height)
+            .map(|r| (0..self.width)
+                .map(|c| {
+                    if get(&round_rocks, r * self.words_per_row, c) { 'O' }
+                    else if get(&self.cube_rocks, r * self.words_per_row, c) { '#' }
+                    else { '.' }
+                })
+                .collect())
+            .collect();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::platform::{Direction, Platform};
+    use super::{get, transpose, BitPlatform};
+
+    const EXAMPLE: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
+    #[test]
+    fn test_tilt_north()
+    {
+        let mut p = BitPlatform::from(EXAMPLE);
+        p.tilt(Direction::North);
+
+        assert_eq!(p.to_string(), "OOOO.#.O..
+OO..#....#
+OO..O##..O
+O..#.OO...
+........#.
+..#....#.#
+..O..#.O.O
+..O.......
+#....###..
+#....#....");
+        assert_eq!(p.load(), 136);
+    }
+
+    #[test]
+    fn test_same_as_platform()
+    {
+        // Wider and taller than a word to check rocks crossing words
+        let wide = EXAMPLE.split('\n')
+            .map(|row| row.repeat(15))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let wide = vec![wide; 8].join("\n");
+
+        let mut bits = BitPlatform::from(wide.as_str());
+        let mut platform = Platform::from(wide.as_str());
+
+        for _ in 0..3
+        {
+            for direction in [Direction::North, Direction::West, Direction::South, Direction::East]
+            {
+                bits.tilt(direction);
+                platform.tilt(direction);
+
+                assert_eq!(bits.load(), platform.load());
+                assert_eq!(BitPlatform::from(platform.to_string().as_str()), bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_cycle()
+    {
+        let mut p = BitPlatform::from(EXAMPLE);
+        p.run_cycle(1_000_000_000);
+
+        assert_eq!(p.load(), 64);
+    }
+
+    #[test]
+    fn test_transpose()
+    {
+        // 70 lines of 130 bits, crossing words both ways
+        let words_per_line = 3;
+        let mut seed: u64 = 14;
+
+        let bits: Vec<u64> = (0..70 * words_per_line)
+            .map(|i| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                // Padding past the 130th bit stays empty
+                if i % words_per_line == 2 { seed & 0b11 } else { seed }
+            })
+            .collect();
+
+        let transposed = transpose(&bits, words_per_line, 130, 2);
+
+        for line in 0..70
+        {
+            for c in 0..130
+            {
+                assert_eq!(get(&transposed, c * 2, line), get(&bits, line * words_per_line, c));
+            }
+        }
+
+        assert_eq!(transpose(&transposed, 2, 70, words_per_line), bits);
+    }
+}
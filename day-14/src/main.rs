@@ -1,8 +1,16 @@
-use std::collections::HashMap;
-
+use bitboard::BitPlatform;
 use platform::{Platform, Direction};
+use program::TiltProgram;
 
+mod bitboard;
 mod platform;
+mod program;
+
+// Switch to false to run the spin cycles on the plain platform
+const BIT_PLATFORM: bool = true;
+// Switch to true to export loads of the first spin cycles as CSV
+const EXPORT_HISTORY: bool = false;
+const HISTORY_CYCLES: usize = 200;
 
 fn main()
 {
@@ -11,17 +19,35 @@ fn main()
     platform.tilt(Direction::North);
     println!("Result: {}", platform.load());
 
-    let mut platform = Platform::from(std::fs::read_to_string("./day-14/input.txt").unwrap().as_str());
-    platform.run_cycle(1_000_000_000, &mut HashMap::new());
+    // Bit packed platform, a lot faster to tilt and to hash while looking for a repetition
+    let result2 = if BIT_PLATFORM
+    {
+        let mut platform = BitPlatform::from(std::fs::read_to_string("./day-14/input.txt").unwrap().as_str());
+        platform.run_cycle(1_000_000_000);
+        platform.load()
+    }
+    else
+    {
+        // Already tilted north, which is where every spin cycle starts anyway
+        platform.run_cycle(1_000_000_000);
+        platform.load()
+    };
 
-    println!("Result 2: {}", platform.load());
+    println!("Result 2: {}", result2);
+
+    if EXPORT_HISTORY
+    {
+        let mut platform = Platform::from(std::fs::read_to_string("./day-14/input.txt").unwrap().as_str());
+        let history = platform.run_with_history(&TiltProgram::spin_cycle(), HISTORY_CYCLES);
+
+        std::fs::write("./day-14/history.csv", history.to_csv()).unwrap();
+        println!("History exported to ./day-14/history.csv");
+    }
 }
 
 #[cfg(test)]
 mod tests
 {
-    use std::collections::HashMap;
-
     use crate::platform::{Platform, Direction};
 
     #[test]
@@ -60,7 +86,7 @@ O.#..O.#.#
 #OO..#...."
         );
 
-        platform.run_cycle(1_000_000_000, &mut HashMap::new());
+        platform.run_cycle(1_000_000_000);
         assert_eq!(platform.load(), 64);
     }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use cycle::{Simulation, Strategy};
+
+use crate::program::SPIN_CYCLE;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
 pub enum Tile
@@ -8,6 +10,7 @@ pub enum Tile
     CubeRock,
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum Direction
 {
     North,
@@ -16,7 +19,7 @@ pub enum Direction
     East
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Platform
 {
     matrix: Vec<Vec<Tile>>
@@ -67,63 +70,41 @@ impl Platform
         }
     }
 
-    pub fn run_cycle(&mut self, cycles: usize, cache: &mut HashMap<Vec<Vec<Tile>>, usize>)
+    pub fn run_cycle(&mut self, cycles: usize)
     {
-        // Find first cycle length
-        let mut n_cycle = 0;
-        let mut ref_cycle = 0;
-
-        while n_cycle < cycles
-        {
-            let current = self.matrix.clone();
-
-            if let Some(cycle) = cache.get(&current)
-            {
-                ref_cycle = *cycle;
-                break;
-            }
-
-            cache.insert(current, n_cycle);
-
-            self.tilt_cycle();
-            n_cycle += 1;
-        }
-
-        let cycles_already_done = n_cycle;
-        let remaining_cycles = cycles - cycles_already_done;
-        let cycle_repeating_interval = cycles_already_done - ref_cycle;
-
-        let skippable_cycles = (remaining_cycles as f64 / cycle_repeating_interval as f64).floor() as usize * cycle_repeating_interval;
-        let remaining_cycles = remaining_cycles - skippable_cycles;
-
-        // println!("[DEBUG] Found repetition between [{}, {}]", ref_cycle, n_cycle - ref_cycle);
-        // println!("[DEBUG] Cycles done: {}, Interval: {}, Can be skipped: {}, Remaining: {}", n_cycle, cycle_repeating_interval, skippable_cycles, remaining_cycles);
-
-        for _ in 0..remaining_cycles
-        {
-            self.tilt_cycle();
-        }
-
+        // Whole matrix as fingerprint, keeping every state seen is the fastest on such small platforms
+        cycle::fast_forward(self, cycles, Strategy::HashMap);
     }
 
     pub fn tilt_cycle(&mut self)
     {
-        for direction in [Direction::North, Direction::West, Direction::South, Direction::East]
+        for direction in SPIN_CYCLE
         {
             self.tilt(direction)
         }
     }
 
+    // Load on the north support beams
     pub fn load(&self) -> u64
+    {
+        self.load_towards(Direction::North)
+    }
+
+    // Each round rock weighs its distance to the opposite edge, plus one
+    pub fn load_towards(&self, edge: Direction) -> u64
     {
         let height = self.matrix.len();
 
         self.matrix.iter().enumerate()
-            .map(|(i, row)| {
-                let n_round_rocks = row.iter().filter(|t| **t == Tile::RoundRock).count() as u64;
-
-                (height - i) as u64 * n_round_rocks
-            })
+            .flat_map(|(r, row)| row.iter().enumerate().map(move |(c, t)| (r, c, t)))
+            .filter(|(_, _, t)| **t == Tile::RoundRock)
+            .map(|(r, c, _)| match edge
+            {
+                Direction::North => height - r,
+                Direction::South => r + 1,
+                Direction::West => self.matrix[r].len() - c,
+                Direction::East => c + 1
+            } as u64)
             .sum()
     }
 
@@ -151,6 +132,22 @@ impl Platform
     }
 }
 
+// One step is a full tilt cycle
+impl Simulation for Platform
+{
+    type Fingerprint = Vec<Vec<Tile>>;
+
+    fn step(&mut self)
+    {
+        self.tilt_cycle();
+    }
+
+    fn fingerprint(&self) -> Self::Fingerprint
+    {
+        self.matrix.clone()
+    }
+}
+
 impl From<&str> for Platform
 {
     fn from(value: &str) -> Self
@@ -171,6 +168,25 @@ impl From<&str> for Platform
     }
 }
 
+impl std::fmt::Display for Platform
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        let rows: Vec<String> = self.matrix.iter()
+            .map(|row| row.iter()
+                .map(|t| match t
+                {
+                    Tile::RoundRock => 'O',
+                    Tile::CubeRock => '#',
+                    Tile::Empty => '.'
+                })
+                .collect())
+            .collect();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
 #[cfg(test)]
 mod tests
 {
@@ -0,0 +1,169 @@
+use crate::platform::{Direction, Platform};
+
+pub const SPIN_CYCLE: [Direction; 4] = [Direction::North, Direction::West, Direction::South, Direction::East];
+
+// Edges the loads are measured against
+const EDGES: [Direction; 4] = SPIN_CYCLE;
+
+// Sequence of tilts, written as letters: "NWSE" is the spin cycle
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TiltProgram
+{
+    pub tilts: Vec<Direction>
+}
+
+// Loads against every edge, after each tilt
+#[derive(PartialEq, Eq, Debug)]
+pub struct LoadEntry
+{
+    pub step: usize,
+    // None for the platform before any tilt
+    pub direction: Option<Direction>,
+    // In the same order as EDGES
+    pub loads: [u64; 4]
+}
+
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct LoadHistory
+{
+    pub entries: Vec<LoadEntry>
+}
+
+impl TiltProgram
+{
+    pub fn spin_cycle() -> Self
+    {
+        TiltProgram { tilts: SPIN_CYCLE.to_vec() }
+    }
+}
+
+impl From<&str> for TiltProgram
+{
+    fn from(value: &str) -> Self
+    {
+        let tilts = value.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c.to_ascii_uppercase()
+            {
+                'N' => Direction::North,
+                'W' => Direction::West,
+                'S' => Direction::South,
+                'E' => Direction::East,
+                _ => panic!("Unknown tilt direction: {}", c)
+            })
+            .collect();
+
+        TiltProgram { tilts }
+    }
+}
+
+impl LoadHistory
+{
+    pub fn to_csv(&self) -> String
+    {
+        let mut csv = String::from("step,direction,north,west,south,east\n");
+
+        for entry in &self.entries
+        {
+            let direction = match entry.direction
+            {
+                Some(Direction::North) => "N",
+                Some(Direction::West) => "W",
+                Some(Direction::South) => "S",
+                Some(Direction::East) => "E",
+                None => ""
+            };
+
+            let loads: Vec<String> = entry.loads.iter().map(|l| l.to_string()).collect();
+
+            csv.push_str(&format!("{},{},{}\n", entry.step, direction, loads.join(",")));
+        }
+
+        csv
+    }
+}
+
+impl Platform
+{
+    // Runs the program `repeat` times, recording loads before the first tilt and after each one
+    pub fn run_with_history(&mut self, program: &TiltProgram, repeat: usize) -> LoadHistory
+    {
+        let mut history = LoadHistory::default();
+        history.entries.push(self.load_entry(0, None));
+
+        for (step, direction) in program.tilts.iter().cycle().take(program.tilts.len() * repeat).enumerate()
+        {
+            self.tilt(*direction);
+            history.entries.push(self.load_entry(step + 1, Some(*direction)));
+        }
+
+        history
+    }
+
+    fn load_entry(&self, step: usize, direction: Option<Direction>) -> LoadEntry
+    {
+        LoadEntry { step, direction, loads: EDGES.map(|edge| self.load_towards(edge)) }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::platform::{Direction, Platform};
+    use super::{TiltProgram, LoadHistory};
+
+    const EXAMPLE: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
+    #[test]
+    fn test_parse_program()
+    {
+        assert_eq!(TiltProgram::from("nwse"), TiltProgram::spin_cycle());
+        assert_eq!(TiltProgram::from("NNE").tilts, vec![Direction::North, Direction::North, Direction::East]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown tilt direction: X")]
+    fn test_parse_invalid_program()
+    {
+        let _ = TiltProgram::from("NX");
+    }
+
+    #[test]
+    fn test_history()
+    {
+        let mut platform = Platform::from(EXAMPLE);
+        let history = platform.run_with_history(&TiltProgram::spin_cycle(), 3);
+
+        assert_eq!(history.entries.len(), 13);
+        assert_eq!(history.entries[0].direction, None);
+        assert_eq!(history.entries[1].loads[0], 136);
+        // Loads after each spin cycle, from the puzzle statement
+        assert_eq!(history.entries[4].loads[0], 87);
+        assert_eq!(history.entries[12].loads[0], platform.load());
+
+        let mut other = Platform::from(EXAMPLE);
+        other.run_cycle(3);
+        assert_eq!(other, platform);
+    }
+
+    #[test]
+    fn test_csv()
+    {
+        let history = Platform::from("O.\n.#").run_with_history(&TiltProgram::from("E"), 1);
+
+        assert_eq!(history.to_csv(), "step,direction,north,west,south,east
+0,,2,2,1,1
+1,E,2,1,1,2
+");
+        assert_eq!(LoadHistory::default().to_csv().lines().count(), 1);
+    }
+}
@@ -1,31 +1,20 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::fmt::Debug;
 
-use crate::hash;
+use crate::{journal::{Journal, JournalEntry}, lens_map::LensMap};
 
-#[derive(PartialEq, Eq, Debug)]
-enum Op
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Op
 {
     Set,
     Remove
 }
 
-#[derive(Clone)]
-struct Lens
-{
-    label: String,
-    focal: u32
-}
-
-#[derive(Clone)]
-struct Box
-{
-    lenses: Vec<Lens>,
-    indexes: HashMap<String, usize>
-}
-
 pub struct Factory
 {
-    boxes: [Box; 256]
+    // Lens labels to focal lengths
+    boxes: LensMap<String, u32>,
+    // Only kept when asked for, recording copies the target box before and after each instruction
+    journal: Option<Journal>
 }
 
 impl Factory
@@ -33,7 +22,16 @@ impl Factory
     pub fn new() -> Self
     {
         Factory {
-            boxes: [(); 256].map(|_| Box::new())
+            boxes: LensMap::new(),
+            journal: None
+        }
+    }
+
+    pub fn with_journal() -> Self
+    {
+        Factory {
+            boxes: LensMap::new(),
+            journal: Some(Journal::default())
         }
     }
 
@@ -42,12 +40,24 @@ impl Factory
         for inst in instructions.split(',')
         {
             let (label, op, focal) = self.parse_inst(inst);
-            let target_box = hash(&label) as usize;
 
             debug!("Instruction: {}", inst);
-            debug!("Box: {}, Label: {}, Op: {:?}, Focal: {:?})", target_box, label, op, focal);
+            debug!("Box: {}, Label: {}, Op: {:?}, Focal: {:?} (was {:?})", self.boxes.bucket_of(&label), label, op, focal, self.boxes.get(&label));
+
+            match &mut self.journal
+            {
+                Some(journal) =>
+                {
+                    let target_box = self.boxes.bucket_of(&label);
+                    let before = self.boxes.bucket(target_box).to_vec();
+
+                    Self::run_one(&mut self.boxes, label.clone(), op, focal);
 
-            self.run_one(target_box, label, op, focal);
+                    let after = self.boxes.bucket(target_box).to_vec();
+                    journal.record(JournalEntry { instruction: inst.to_string(), label, op, focal, target_box, before, after });
+                }
+                None => Self::run_one(&mut self.boxes, label, op, focal)
+            }
 
             debug!("\n{:?}", self);
         }
@@ -71,118 +81,58 @@ impl Factory
         (label, op, focal)
     }
 
-    fn run_one(&mut self, target_box: usize, label: String, op: Op, focal: Option<u32>)
+    fn run_one(boxes: &mut LensMap<String, u32>, label: String, op: Op, focal: Option<u32>)
     {
         match op
         {
-            Op::Remove => self.boxes[target_box].remove(&label),
-            Op::Set => {
-                if self.boxes[target_box].contains(&label)
-                {
-                    self.boxes[target_box].replace(&label, Lens { label: label.clone(), focal: focal.unwrap() })
-                }
-                else
-                {
-                    self.boxes[target_box].push(Lens { label: label.clone(), focal: focal.unwrap() })
-                }
+            Op::Remove => { boxes.remove(&label); },
+            Op::Set =>
+            {
+                let focal = focal.unwrap();
+                boxes.entry(label).and_modify(|v| *v = focal).or_insert(focal);
             }
         }
     }
 
     pub fn focusing_power(&self) -> u32
     {
-        self.boxes.iter().enumerate().fold(0, |acc, (i, target_box)| {
-            acc + (1 + i as u32) * target_box.focusing_power()
-        })
-    }
-}
-
-impl Debug for Factory
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
-    {
-        f.write_str("---\n")?;
-
-        for (i, target_box) in self.boxes.iter().enumerate().filter(|b| !b.1.lenses.is_empty())
-        {
-            f.write_fmt(format_args!("Box {}: {:?}\n", i, target_box))?;
-        }
-
-        Ok(())
-    }
-}
-
-impl Box
-{
-    pub fn new() -> Self
-    {
-        Box {
-            lenses: vec![],
-            indexes: HashMap::new()
-        }
+        self.boxes.focusing_power()
     }
 
-    fn contains(&self, label: &String) -> bool
+    pub fn lenses(&self) -> &LensMap<String, u32>
     {
-        self.indexes.contains_key(label)
+        &self.boxes
     }
 
-    fn push(&mut self, lens: Lens)
+    // None unless the factory was built with a journal
+    pub fn journal(&self) -> Option<&Journal>
     {
-        let label = lens.label.clone();
-
-        self.lenses.push(lens);
-        self.indexes.insert(label, self.lenses.len() - 1);
+        self.journal.as_ref()
     }
 
-    fn replace(&mut self, label: &String, lens: Lens)
+    // Puts the box of the last applied instruction back as it was, returns that instruction
+    pub fn undo(&mut self) -> Option<&JournalEntry>
     {
-        if !self.indexes.contains_key(label)
-        {
-            panic!("Box does not contains lens '{}'", label);
-        }
-
-        let index = self.indexes[label];
-        self.lenses[index] = lens;
+        self.journal.as_mut()?.undo(&mut self.boxes)
     }
 
-    fn remove(&mut self, label: &String)
+    pub fn redo(&mut self) -> Option<&JournalEntry>
     {
-        if !self.indexes.contains_key(label) { return }
-
-        let index = self.indexes[label];
-
-        self.lenses.remove(index);
-        self.indexes.remove(label);
-
-        let indexes_to_update: Vec<_> = self.indexes.iter()
-            .filter(|(_, i)| **i > index)
-            .map(|(k, _)| k.clone())
-            .collect();
-
-        for l in indexes_to_update
-        {
-            self.indexes.entry(l).and_modify(|e| *e -= 1);
-        }
+        self.journal.as_mut()?.redo(&mut self.boxes)
     }
 
-    pub fn focusing_power(&self) -> u32
+    // Every box after the first `index` instructions
+    pub fn snapshot(&self, index: usize) -> Option<LensMap<String, u32>>
     {
-        self.lenses.iter().enumerate().fold(0, |acc, (i, lens)| {
-            acc + (1 + i as u32) * lens.focal
-        })
+        Some(self.journal.as_ref()?.snapshot(index, self.boxes.bucket_count()))
     }
 }
 
-impl Debug for Box
+impl Debug for Factory
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
-        for lens in self.lenses.iter()
-        {
-            f.write_fmt(format_args!("[{} {}] ", lens.label, lens.focal))?;
-        }
-
-        Ok(())
+        f.write_str("---\n")?;
+        f.write_fmt(format_args!("{:?}", self.boxes))
     }
-}
\ No newline at end of file
+}
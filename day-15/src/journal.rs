@@ -0,0 +1,203 @@
+use crate::{hashmap::Op, lens_map::LensMap};
+
+pub type BoxContent = Vec<(String, u32)>;
+
+// One applied instruction, with the content of its box around it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct JournalEntry
+{
+    pub instruction: String,
+    pub label: String,
+    pub op: Op,
+    pub focal: Option<u32>,
+
+    pub target_box: usize,
+    pub before: BoxContent,
+    pub after: BoxContent
+}
+
+// Every instruction run so far, entries after `applied` are the ones that can be redone
+#[derive(Debug, Default)]
+pub struct Journal
+{
+    entries: Vec<JournalEntry>,
+    applied: usize
+}
+
+impl JournalEntry
+{
+    pub fn changed(&self) -> bool
+    {
+        self.before != self.after
+    }
+}
+
+impl Journal
+{
+    pub fn len(&self) -> usize
+    {
+        self.applied
+    }
+
+    // Applied entries only
+    pub fn entries(&self) -> &[JournalEntry]
+    {
+        &self.entries[..self.applied]
+    }
+
+    // Recording a new instruction forgets what could be redone
+    pub fn record(&mut self, entry: JournalEntry)
+    {
+        self.entries.truncate(self.applied);
+        self.entries.push(entry);
+        self.applied += 1;
+    }
+
+    pub fn undo(&mut self, boxes: &mut LensMap<String, u32>) -> Option<&JournalEntry>
+    {
+        if self.applied == 0 { return None }
+
+        self.applied -= 1;
+
+        let entry = &self.entries[self.applied];
+        boxes.replace_bucket(entry.target_box, entry.before.clone());
+
+        Some(entry)
+    }
+
+    pub fn redo(&mut self, boxes: &mut LensMap<String, u32>) -> Option<&JournalEntry>
+    {
+        let entry = self.entries.get(self.applied)?;
+        boxes.replace_bucket(entry.target_box, entry.after.clone());
+
+        self.applied += 1;
+
+        Some(entry)
+    }
+
+    // Boxes as they were once the first `index` instructions were applied
+    pub fn snapshot(&self, index: usize, bucket_count: usize) -> LensMap<String, u32>
+    {
+        let mut boxes = LensMap::with_buckets(bucket_count);
+
+        for entry in &self.entries()[..index.min(self.applied)]
+        {
+            boxes.replace_bucket(entry.target_box, entry.after.clone());
+        }
+
+        boxes
+    }
+
+    // Index of the instruction that put the lens with this label where it is now, None if it is not in a box
+    pub fn introduced_at(&self, label: &str) -> Option<usize>
+    {
+        let entry = self.entries()
+            .iter()
+            .rposition(|e| e.label == label && e.changed())?;
+
+        if self.entries[entry].op == Op::Remove { return None }
+
+        // A new focal length for a lens already in place does not move it, look for when it was added
+        self.entries()[..=entry]
+            .iter()
+            .rposition(|e| e.label == label && !contains(&e.before, label) && contains(&e.after, label))
+    }
+
+    // Every applied instruction touching this label
+    pub fn history_of<'a>(&'a self, label: &'a str) -> impl Iterator<Item = (usize, &'a JournalEntry)>
+    {
+        self.entries()
+            .iter()
+            .enumerate()
+            .filter(move |(_, e)| e.label == label)
+    }
+}
+
+fn contains(content: &BoxContent, label: &str) -> bool
+{
+    content.iter().any(|(l, _)| l == label)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::hashmap::Factory;
+
+    const EXAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    #[test]
+    fn test_undo_redo()
+    {
+        let mut factory = Factory::with_journal();
+        factory.run(EXAMPLE);
+
+        assert_eq!(factory.journal().unwrap().len(), 11);
+        assert_eq!(factory.focusing_power(), 145);
+
+        // ot=7 then pc=6
+        assert_eq!(factory.undo().unwrap().instruction, "ot=7");
+        factory.undo();
+        assert_eq!(factory.focusing_power(), 1 + 2 * 2 + 4 * (9 + 2 * 5));
+
+        assert_eq!(factory.redo().unwrap().instruction, "pc=6");
+        factory.redo();
+        assert!(factory.redo().is_none());
+        assert_eq!(factory.focusing_power(), 145);
+
+        while factory.undo().is_some() {}
+        assert_eq!(factory.focusing_power(), 0);
+
+        // New instruction drops what could be redone
+        factory.run("rn=2");
+        assert!(factory.redo().is_none());
+        assert_eq!(factory.journal().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_without_journal()
+    {
+        let mut factory = Factory::new();
+        factory.run(EXAMPLE);
+
+        assert_eq!(factory.focusing_power(), 145);
+        assert!(factory.journal().is_none());
+        assert!(factory.undo().is_none());
+        assert!(factory.snapshot(4).is_none());
+    }
+
+    #[test]
+    fn test_snapshot()
+    {
+        let mut factory = Factory::with_journal();
+        factory.run(EXAMPLE);
+
+        let snapshot = factory.snapshot(4).unwrap();
+        assert_eq!(snapshot.bucket(0), &[("rn".to_string(), 1), ("cm".to_string(), 2)]);
+        assert_eq!(snapshot.bucket(1), &[("qp".to_string(), 3)]);
+
+        assert_eq!(factory.snapshot(0).unwrap().len(), 0);
+        assert_eq!(factory.snapshot(11).unwrap().focusing_power(), 145);
+        assert_eq!(factory.snapshot(100).unwrap().focusing_power(), 145);
+
+        let entry = &factory.journal().unwrap().entries()[4];
+        assert_eq!(entry.instruction, "qp-");
+        assert_eq!(entry.before, vec![("qp".to_string(), 3)]);
+        assert!(entry.after.is_empty());
+    }
+
+    #[test]
+    fn test_introduced_at()
+    {
+        let mut factory = Factory::with_journal();
+        factory.run(EXAMPLE);
+
+        // pc was added at 5, removed at 8 then added again
+        assert_eq!(factory.journal().unwrap().introduced_at("pc"), Some(9));
+        // ot changed focal length at 10, but is there since 6
+        assert_eq!(factory.journal().unwrap().introduced_at("ot"), Some(6));
+        assert_eq!(factory.journal().unwrap().introduced_at("qp"), None);
+        assert_eq!(factory.journal().unwrap().introduced_at("zz"), None);
+
+        assert_eq!(factory.journal().unwrap().history_of("cm").map(|(i, _)| i).collect::<Vec<_>>(), vec![1, 3]);
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt::Debug;
+
+pub const DEFAULT_BUCKETS: usize = 256;
+
+// HASH algorithm, reduced on the number of buckets instead of 256
+pub fn hash_in(value: &str, buckets: usize) -> usize
+{
+    value.chars()
+        .fold(0, |acc, c| {
+            (acc + (c as usize)) * 17 % buckets
+        })
+}
+
+// HASHMAP procedure as a map: keys are spread in buckets by their HASH, and keep their insertion order in it
+#[derive(Clone)]
+pub struct LensMap<K, V>
+{
+    buckets: Vec<Vec<(K, V)>>,
+    len: usize
+}
+
+pub enum Entry<'a, K, V>
+{
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>)
+}
+
+pub struct OccupiedEntry<'a, K, V>
+{
+    slot: &'a mut (K, V)
+}
+
+pub struct VacantEntry<'a, K, V>
+{
+    bucket: &'a mut Vec<(K, V)>,
+    len: &'a mut usize,
+    key: K
+}
+
+impl<K: AsRef<str>, V> LensMap<K, V>
+{
+    pub fn new() -> Self
+    {
+        Self::with_buckets(DEFAULT_BUCKETS)
+    }
+
+    pub fn with_buckets(buckets: usize) -> Self
+    {
+        if buckets == 0 { panic!("A lens map needs at least one bucket") }
+
+        LensMap { buckets: (0..buckets).map(|_| vec![]).collect(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.len
+    }
+
+    pub fn bucket_count(&self) -> usize
+    {
+        self.buckets.len()
+    }
+
+    pub fn bucket_of(&self, key: &str) -> usize
+    {
+        hash_in(key, self.buckets.len())
+    }
+
+    // Content of a bucket, in insertion order
+    pub fn bucket(&self, index: usize) -> &[(K, V)]
+    {
+        &self.buckets[index]
+    }
+
+    // Whole content of a bucket at once, keys must belong to it
+    pub fn replace_bucket(&mut self, index: usize, content: Vec<(K, V)>)
+    {
+        if let Some((key, _)) = content.iter().find(|(k, _)| self.bucket_of(k.as_ref()) != index)
+        {
+            panic!("Key '{}' does not belong to bucket {}", key.as_ref(), index);
+        }
+
+        self.len = self.len - self.buckets[index].len() + content.len();
+        self.buckets[index] = content;
+    }
+
+    // Values behind the removed one move forward
+    pub fn remove(&mut self, key: &str) -> Option<V>
+    {
+        let bucket = self.bucket_of(key);
+        let index = self.position(bucket, key)?;
+
+        self.len -= 1;
+
+        Some(self.buckets[bucket].remove(index).1)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V>
+    {
+        let bucket = self.bucket_of(key);
+        let index = self.position(bucket, key)?;
+
+        Some(&self.buckets[bucket][index].1)
+    }
+
+    // Modifying a value through its entry keeps its place in the bucket
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V>
+    {
+        let bucket = self.bucket_of(key.as_ref());
+
+        match self.position(bucket, key.as_ref())
+        {
+            Some(index) => Entry::Occupied(OccupiedEntry { slot: &mut self.buckets[bucket][index] }),
+            None => Entry::Vacant(VacantEntry { bucket: &mut self.buckets[bucket], len: &mut self.len, key })
+        }
+    }
+
+    // Bucket by bucket, in insertion order inside each bucket
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)>
+    {
+        self.buckets.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    fn position(&self, bucket: usize, key: &str) -> Option<usize>
+    {
+        self.buckets[bucket].iter().position(|(k, _)| k.as_ref() == key)
+    }
+}
+
+impl<K> LensMap<K, u32>
+{
+    // Sum of (1 + bucket) * (1 + slot) * focal length
+    pub fn focusing_power(&self) -> u32
+    {
+        self.buckets.iter().enumerate().fold(0, |acc, (i, bucket)| {
+            acc + (1 + i as u32) * bucket.iter().enumerate().fold(0, |acc, (j, (_, focal))| acc + (1 + j as u32) * focal)
+        })
+    }
+}
+
+impl<K: AsRef<str>, V> Default for LensMap<K, V>
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for LensMap<K, V>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        for (i, bucket) in self.buckets.iter().enumerate().filter(|(_, b)| !b.is_empty())
+        {
+            f.write_fmt(format_args!("Box {}: ", i))?;
+
+            for (key, value) in bucket
+            {
+                f.write_fmt(format_args!("[{:?} {:?}] ", key, value))?;
+            }
+
+            f.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+{
+    pub fn or_insert(self, default: V) -> &'a mut V
+    {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V
+    {
+        match self
+        {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default())
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self
+    {
+        if let Entry::Occupied(entry) = &mut self
+        {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V>
+{
+    pub fn get_mut(&mut self) -> &mut V
+    {
+        &mut self.slot.1
+    }
+
+    pub fn into_mut(self) -> &'a mut V
+    {
+        &mut self.slot.1
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+{
+    // New values go at the back of their bucket
+    pub fn insert(self, value: V) -> &'a mut V
+    {
+        *self.len += 1;
+        self.bucket.push((self.key, value));
+
+        &mut self.bucket.last_mut().unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{hash_in, Entry, LensMap};
+
+    #[test]
+    fn test_hash_in()
+    {
+        assert_eq!(hash_in("HASH", 256), 52);
+        assert_eq!(hash_in("rn", 256), 0);
+        assert_eq!(hash_in("qp", 256), 1);
+        assert!((0..100).all(|i| hash_in(&format!("key{}", i), 7) < 7));
+    }
+
+    #[test]
+    fn test_entry_get_remove()
+    {
+        let mut map: LensMap<String, u32> = LensMap::new();
+
+        map.entry("rn".to_string()).or_insert(1);
+        map.entry("cm".to_string()).or_insert(2);
+        map.entry("rn".to_string()).and_modify(|v| *v = 3).or_insert(3);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("rn"), Some(&3));
+        assert_eq!(map.get("qp"), None);
+
+        // "rn" and "cm" are both in box 0, replacing keeps the order
+        let keys: Vec<&String> = map.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["rn", "cm"]);
+
+        assert_eq!(map.remove("rn"), Some(3));
+        assert_eq!(map.remove("rn"), None);
+        assert_eq!(map.bucket(0), &[("cm".to_string(), 2)]);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_entry()
+    {
+        let mut map: LensMap<&str, u32> = LensMap::with_buckets(4);
+
+        *map.entry("a").or_insert(0) += 1;
+        *map.entry("a").or_insert(0) += 1;
+        map.entry("b").and_modify(|v| *v = 10).or_insert_with(|| 5);
+        map.entry("b").and_modify(|v| *v = 10).or_insert_with(|| 5);
+
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&10));
+        assert_eq!(map.bucket_count(), 4);
+
+        assert!(matches!(map.entry("c"), Entry::Vacant(_)));
+        assert!(matches!(map.entry("a"), Entry::Occupied(_)));
+
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_focusing_power()
+    {
+        let mut map: LensMap<&str, u32> = LensMap::new();
+
+        // rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7
+        set(&mut map, "rn", 1);
+        map.remove("cm");
+        set(&mut map, "qp", 3);
+        set(&mut map, "cm", 2);
+        map.remove("qp");
+        set(&mut map, "pc", 4);
+        set(&mut map, "ot", 9);
+        set(&mut map, "ab", 5);
+        map.remove("pc");
+        set(&mut map, "pc", 6);
+        set(&mut map, "ot", 7);
+
+        assert_eq!(map.bucket(3), &[("ot", 7), ("ab", 5), ("pc", 6)]);
+        assert_eq!(map.focusing_power(), 145);
+    }
+
+    // Same as the factory's `label=focal`
+    fn set<'a>(map: &mut LensMap<&'a str, u32>, label: &'a str, focal: u32)
+    {
+        map.entry(label).and_modify(|v| *v = focal).or_insert(focal);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_bucket()
+    {
+        LensMap::<String, u32>::with_buckets(0);
+    }
+}
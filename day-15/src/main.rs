@@ -11,6 +11,8 @@ macro_rules! debug {
 }
 
 mod hashmap;
+mod journal;
+mod lens_map;
 
 use hashmap::Factory;
 
@@ -24,18 +26,51 @@ fn main()
 
     println!("Result: {}", hashes.iter().sum::<u32>());
 
-    let mut factory = Factory::new();
+    // The journal is only read by the debug output
+    let mut factory = if DEBUG { Factory::with_journal() } else { Factory::new() };
     factory.run(&std::fs::read_to_string("./day-15/input.txt").unwrap());
 
     println!("Result 2: {}", factory.focusing_power());
+
+    debug!("{} lenses in the boxes", factory.lenses().len());
+
+    for (label, focal) in factory.lenses().iter()
+    {
+        debug!("{} {} (box {})", label, focal, factory.lenses().bucket_of(label));
+    }
+
+    if let Some(journal) = factory.journal()
+    {
+        for (i, entry) in journal.entries().iter().enumerate().filter(|(_, e)| e.changed())
+        {
+            debug!("#{} {} (box {}): {:?} -> {:?}", i, entry.instruction, entry.target_box, entry.before, entry.after);
+        }
+
+        // Follow the lens of the first instruction
+        let label = content.split(['=', '-']).next().unwrap();
+
+        debug!("{} instructions, {} in place since #{:?}", journal.len(), label, journal.introduced_at(label));
+
+        for (i, entry) in journal.history_of(label)
+        {
+            debug!("#{} {}", i, entry.instruction);
+        }
+
+        // Going back and forth through the journal must end on the same boxes
+        let power = factory.focusing_power();
+        let halfway = factory.snapshot(journal.len() / 2).unwrap();
+
+        while factory.undo().is_some() {}
+        debug!("Undone: {}, halfway: {}", factory.focusing_power(), halfway.focusing_power());
+
+        while factory.redo().is_some() {}
+        assert_eq!(factory.focusing_power(), power);
+    }
 }
 
 fn hash(value: &str) -> u32
 {
-    value.chars()
-        .fold(0, |acc, c| {
-            (acc + (c as u32)) * 17 % 256
-        })
+    lens_map::hash_in(value, 256) as u32
 }
 
 #[cfg(test)]
@@ -0,0 +1,321 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::facility::{Coord, Facility, Vector};
+
+const WORD: usize = 64;
+
+// Beam paths cut at every splitter: a segment goes from where a beam enters (the border or a splitter output)
+// to the next splitter splitting it, and leads to the segments starting at the outputs of that splitter.
+// Segments in a cycle always energize the same tiles, so they are collapsed into components.
+pub struct BeamGraph
+{
+    // Segment starting with each beam state
+    segments: HashMap<(Coord, Vector), usize>,
+    // Component of each segment
+    components: Vec<usize>,
+    // Tiles energized from each component, as bits
+    energized: Vec<Vec<u64>>
+}
+
+impl BeamGraph
+{
+    pub fn new(facility: &Facility, entries: &[(Coord, Vector)]) -> Self
+    {
+        let mut segments = HashMap::new();
+        let mut tiles: Vec<Vec<usize>> = vec![];
+        let mut next_starts: Vec<Vec<(Coord, Vector)>> = vec![];
+
+        let mut queue = entries.to_vec();
+
+        while let Some(start) = queue.pop()
+        {
+            if segments.contains_key(&start) { continue }
+
+            let (segment_tiles, next) = trace(facility, start);
+
+            segments.insert(start, tiles.len());
+            tiles.push(segment_tiles);
+            queue.extend(next.iter().copied());
+            next_starts.push(next);
+        }
+
+        let edges: Vec<Vec<usize>> = next_starts.iter()
+            .map(|next| next.iter().map(|start| segments[start]).collect())
+            .collect();
+
+        let (components, n_components) = strongly_connected_components(&edges);
+
+        // Components are numbered from sinks to sources, what follows a component is always done before it
+        let words = facility.tiles_count().div_ceil(WORD);
+        let mut members: Vec<Vec<usize>> = vec![vec![]; n_components];
+
+        for (segment, component) in components.iter().enumerate()
+        {
+            members[*component].push(segment);
+        }
+
+        let mut energized: Vec<Vec<u64>> = Vec::with_capacity(n_components);
+
+        for segments_of_component in &members
+        {
+            let mut bits = vec![0; words];
+
+            for segment in segments_of_component
+            {
+                for tile in &tiles[*segment]
+                {
+                    bits[tile / WORD] |= 1 << (tile % WORD);
+                }
+
+                for next in &edges[*segment]
+                {
+                    let next_component = components[*next];
+
+                    // Already in the bits when in the same component
+                    if next_component == energized.len() { continue }
+
+                    bits.iter_mut()
+                        .zip(&energized[next_component])
+                        .for_each(|(b, n)| *b |= n);
+                }
+            }
+
+            energized.push(bits);
+        }
+
+        BeamGraph { segments, components, energized }
+    }
+
+    // Number of tiles energized by a beam entering `coord` with `direction`, None if it was not an entry of the graph
+    pub fn energized(&self, coord: &Coord, direction: &Vector) -> Option<usize>
+    {
+        let segment = self.segments.get(&(*coord, *direction))?;
+
+        Some(self.energized[self.components[*segment]]
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum())
+    }
+
+    pub fn segments_count(&self) -> usize
+    {
+        self.components.len()
+    }
+
+    pub fn components_count(&self) -> usize
+    {
+        self.energized.len()
+    }
+}
+
+// Tiles of the segment and starts of the segments following it
+fn trace(facility: &Facility, start: (Coord, Vector)) -> (Vec<usize>, Vec<(Coord, Vector)>)
+{
+    let (mut coord, mut direction) = start;
+
+    let mut tiles = vec![];
+    // Mirrors alone can make a loop
+    let mut seen = HashSet::new();
+
+    loop
+    {
+        if !seen.insert((coord, direction)) { return (tiles, vec![]) }
+
+        tiles.push(facility.tile_index(&coord));
+
+        let outputs = facility.tile(&coord).outputs(&coord, &direction);
+
+        if outputs.len() != 1
+        {
+            let next = outputs.iter()
+                .filter_map(|(exit, d)| facility.next_coord(exit, d).map(|c| (c, *d)))
+                .collect();
+
+            return (tiles, next);
+        }
+
+        let exit;
+        (exit, direction) = outputs[0];
+
+        // Teleported beams energize the tile they come out of too
+        if exit != coord
+        {
+            tiles.push(facility.tile_index(&exit));
+        }
+
+        match facility.next_coord(&exit, &direction)
+        {
+            Some(next_coord) => coord = next_coord,
+            None => return (tiles, vec![])
+        }
+    }
+}
+
+// Tarjan's algorithm, with an explicit call stack
+// Components are numbered in the order they are completed, so an edge never goes to a bigger number
+fn strongly_connected_components(edges: &[Vec<usize>]) -> (Vec<usize>, usize)
+{
+    let n = edges.len();
+
+    let mut index = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+
+    let mut components = vec![usize::MAX; n];
+    let mut next_index = 0;
+    let mut n_components = 0;
+
+    for root in 0..n
+    {
+        if index[root] != usize::MAX { continue }
+
+        // Node and next edge to look at
+        let mut calls = vec![(root, 0)];
+
+        index[root] = next_index;
+        low[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((v, edge)) = calls.last().copied()
+        {
+            if edge < edges[v].len()
+            {
+                calls.last_mut().unwrap().1 += 1;
+
+                let w = edges[v][edge];
+
+                if index[w] == usize::MAX
+                {
+                    index[w] = next_index;
+                    low[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+
+                    calls.push((w, 0));
+                }
+                else if on_stack[w]
+                {
+                    low[v] = low[v].min(index[w]);
+                }
+
+                continue;
+            }
+
+            calls.pop();
+
+            if let Some((parent, _)) = calls.last()
+            {
+                low[*parent] = low[*parent].min(low[v]);
+            }
+
+            if low[v] == index[v]
+            {
+                loop
+                {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    components[w] = n_components;
+
+                    if w == v { break }
+                }
+
+                n_components += 1;
+            }
+        }
+    }
+
+    (components, n_components)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::facility::{Facility, Coord, RIGHT, UP};
+    use super::{BeamGraph, strongly_connected_components};
+
+    const EXAMPLE: &str = r#".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|...."#;
+
+    fn assert_same_as_beam(content: &str)
+    {
+        let mut facility = Facility::from(content);
+        let entries = facility.edge_entries();
+        let graph = BeamGraph::new(&facility, &entries);
+
+        for (coord, direction) in entries
+        {
+            facility.start_beam(coord, direction);
+            assert_eq!(graph.energized(&coord, &direction), Some(facility.energized_tiles_count()), "{:?} {:?}", coord, direction);
+        }
+    }
+
+    #[test]
+    fn test_example()
+    {
+        let facility = Facility::from(EXAMPLE);
+        let graph = BeamGraph::new(&facility, &[(Coord::new(0, 0), RIGHT)]);
+
+        assert_eq!(graph.energized(&Coord::new(0, 0), &RIGHT), Some(46));
+        assert_eq!(graph.energized(&Coord::new(9, 0), &UP), None);
+        assert!(graph.components_count() <= graph.segments_count());
+
+        assert_same_as_beam(EXAMPLE);
+    }
+
+    #[test]
+    fn test_generated()
+    {
+        let symbols = ['.', '.', '.', '/', '\\', '|', '-'];
+        let mut seed: u64 = 16;
+
+        let content: Vec<String> = (0..40)
+            .map(|_| (0..40)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    symbols[(seed >> 33) as usize % symbols.len()]
+                })
+                .collect())
+            .collect();
+
+        assert_same_as_beam(&content.join("\n"));
+    }
+
+    #[test]
+    fn test_portals()
+    {
+        assert_same_as_beam("A.|..\n..B..\n/..A.\n.B..-");
+    }
+
+    #[test]
+    fn test_components()
+    {
+        // 0 -> 1 <-> 2 -> 3
+        let (components, n) = strongly_connected_components(&[vec![1], vec![2], vec![1, 3], vec![]]);
+
+        assert_eq!(n, 3);
+        assert_eq!(components[1], components[2]);
+        assert!(components[3] < components[1] && components[1] < components[0]);
+    }
+
+    #[test]
+    fn test_mirror_loop()
+    {
+        // Beam going around between 4 mirrors forever
+        let facility = Facility::from("/.\\\n...\n\\./");
+        let graph = BeamGraph::new(&facility, &[(Coord::new(0, 1), RIGHT)]);
+
+        assert_eq!(graph.energized(&Coord::new(0, 1), &RIGHT), Some(8));
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt::Debug;
+
+use crate::facility::{Coord, Vector, UP, DOWN, LEFT, RIGHT};
+
+// Anything a beam can go through, each one decides where the beam goes next
+pub trait Component: Debug
+{
+    fn symbol(&self) -> char;
+
+    // Beams leaving the tile at `coord` entered with `direction`:
+    // where each one leaves from (the tile itself unless teleported) and which way it goes
+    fn outputs(&self, coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>;
+
+    // Tiles reporting how many beams went through them
+    fn counts_beams(&self) -> bool
+    {
+        false
+    }
+}
+
+#[derive(Debug)]
+pub struct Empty;
+
+// '/' when `slash`, '\' otherwise
+#[derive(Debug)]
+pub struct Mirror
+{
+    pub slash: bool
+}
+
+// Splits beams hitting its flat side, '|' when `vertical`, '-' otherwise
+#[derive(Debug)]
+pub struct Splitter
+{
+    pub vertical: bool
+}
+
+// Only lets beams going its way through, absorbs the others
+#[derive(Debug)]
+pub struct Filter
+{
+    pub direction: Vector
+}
+
+#[derive(Debug)]
+pub struct Absorber;
+
+// Lets everything through, Facility::counter_directions tells which ways beams went through it
+#[derive(Debug)]
+pub struct Counter;
+
+// Beams come out of the other end, going the same way
+#[derive(Debug)]
+pub struct Portal
+{
+    pub symbol: char,
+    pub target: Coord
+}
+
+impl Component for Empty
+{
+    fn symbol(&self) -> char
+    {
+        '.'
+    }
+
+    fn outputs(&self, coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        vec![(*coord, *direction)]
+    }
+}
+
+impl Component for Mirror
+{
+    fn symbol(&self) -> char
+    {
+        if self.slash { '/' } else { '\\' }
+    }
+
+    fn outputs(&self, coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        let reflected = match (self.slash, direction)
+        {
+            (true, &UP) | (false, &DOWN) => RIGHT,
+            (true, &DOWN) | (false, &UP) => LEFT,
+            (true, &LEFT) | (false, &RIGHT) => DOWN,
+            (true, &RIGHT) | (false, &LEFT) => UP,
+            _ => panic!("Unknown direction")
+        };
+
+        vec![(*coord, reflected)]
+    }
+}
+
+impl Component for Splitter
+{
+    fn symbol(&self) -> char
+    {
+        if self.vertical { '|' } else { '-' }
+    }
+
+    fn outputs(&self, coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        match (self.vertical, direction)
+        {
+            (true, &LEFT | &RIGHT) => vec![(*coord, UP), (*coord, DOWN)],
+            (false, &UP | &DOWN) => vec![(*coord, LEFT), (*coord, RIGHT)],
+            _ => vec![(*coord, *direction)]
+        }
+    }
+}
+
+impl Component for Filter
+{
+    fn symbol(&self) -> char
+    {
+        match self.direction
+        {
+            UP => '^',
+            DOWN => 'v',
+            LEFT => '<',
+            _ => '>'
+        }
+    }
+
+    fn outputs(&self, coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        if *direction == self.direction { vec![(*coord, *direction)] }
+        else { vec![] }
+    }
+}
+
+impl Component for Absorber
+{
+    fn symbol(&self) -> char
+    {
+        '#'
+    }
+
+    fn outputs(&self, _coord: &Coord, _direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        vec![]
+    }
+}
+
+impl Component for Counter
+{
+    fn symbol(&self) -> char
+    {
+        '+'
+    }
+
+    fn outputs(&self, coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        vec![(*coord, *direction)]
+    }
+
+    fn counts_beams(&self) -> bool
+    {
+        true
+    }
+}
+
+impl Component for Portal
+{
+    fn symbol(&self) -> char
+    {
+        self.symbol
+    }
+
+    fn outputs(&self, _coord: &Coord, direction: &Vector) -> Vec<(Coord, Vector)>
+    {
+        vec![(self.target, *direction)]
+    }
+}
+
+// Every component but portals, which need to know where their other end is
+pub fn component(symbol: char) -> Option<Box<dyn Component>>
+{
+    let component: Box<dyn Component> = match symbol
+    {
+        '.'  => Box::new(Empty),
+        '/'  => Box::new(Mirror { slash: true }),
+        '\\' => Box::new(Mirror { slash: false }),
+        '|'  => Box::new(Splitter { vertical: true }),
+        '-'  => Box::new(Splitter { vertical: false }),
+        '^'  => Box::new(Filter { direction: UP }),
+        'v'  => Box::new(Filter { direction: DOWN }),
+        '<'  => Box::new(Filter { direction: LEFT }),
+        '>'  => Box::new(Filter { direction: RIGHT }),
+        '#'  => Box::new(Absorber),
+        '+'  => Box::new(Counter),
+        _    => return None
+    };
+
+    Some(component)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::facility::{Coord, Facility, RIGHT, DOWN, LEFT};
+    use super::component;
+
+    #[test]
+    fn test_symbols()
+    {
+        for symbol in ['.', '/', '\\', '|', '-', '^', 'v', '<', '>', '#', '+']
+        {
+            assert_eq!(component(symbol).unwrap().symbol(), symbol);
+        }
+
+        assert!(component('A').is_none());
+    }
+
+    #[test]
+    fn test_filters_and_absorbers()
+    {
+        let mut facility = Facility::from("..>..\n..#..");
+
+        facility.start_beam(Coord::new(0, 0), RIGHT);
+        assert_eq!(facility.energized_tiles_count(), 5);
+
+        facility.start_beam(Coord::new(0, 4), LEFT);
+        assert_eq!(facility.energized_tiles_count(), 3);
+
+        facility.start_beam(Coord::new(1, 0), RIGHT);
+        assert_eq!(facility.energized_tiles_count(), 3);
+    }
+
+    #[test]
+    fn test_portals()
+    {
+        let mut facility = Facility::from("A....\n.....\n...A.");
+
+        // Enters the first end, comes out of the second one (energizing it) and keeps going right
+        facility.start_beam(Coord::new(0, 0), RIGHT);
+        assert_eq!(facility.energized_tiles_count(), 3);
+
+        facility.start_beam(Coord::new(0, 3), DOWN);
+        assert_eq!(facility.energized_tiles_count(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown tile")]
+    fn test_unknown_tile()
+    {
+        let _ = Facility::from("..?..");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_single_portal()
+    {
+        let _ = Facility::from("A..");
+    }
+
+    #[test]
+    fn test_counters()
+    {
+        let mut facility = Facility::from(".|.\n.+.\n-+-");
+
+        facility.start_beam(Coord::new(0, 0), RIGHT);
+        assert_eq!(facility.counter_directions(), vec![(Coord::new(1, 1), 1), (Coord::new(2, 1), 1)]);
+
+        // Same with an absorber instead of the first counter
+        let mut facility = Facility::from(".|.\n.#.\n-+-");
+
+        facility.start_beam(Coord::new(0, 0), RIGHT);
+        assert_eq!(facility.counter_directions(), vec![(Coord::new(2, 1), 0)]);
+        assert_eq!(facility.energized_tiles_count(), 3);
+    }
+}
@@ -1,6 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
+use crate::{beam_graph::BeamGraph, components::{component, Component, Empty, Portal}};
+
 pub const UP: Vector    = Vector::new(-1, 0);
 pub const DOWN: Vector  = Vector::new(1, 0);
 pub const LEFT: Vector  = Vector::new(0, -1);
@@ -20,20 +22,12 @@ pub struct Vector
     y: i8
 }
 
-#[derive(Debug)]
-pub enum Tile
-{
-    Empty,
-    MirrorSlash,
-    MirrorAntiSlash,
-    VerticalSplitter,
-    HorizontalSplitter
-}
-
 pub struct Facility
 {
-    matrix: Vec<Vec<Tile>>,
-    visited_tiles: HashSet<(Coord, Vector)>
+    matrix: Vec<Vec<Box<dyn Component>>>,
+    visited_tiles: HashSet<(Coord, Vector)>,
+    // Tiles a beam came out of without going through them, like the other end of a portal
+    exit_tiles: HashSet<Coord>
 }
 
 impl Vector
@@ -74,118 +68,88 @@ impl Facility
     pub fn start_beam(&mut self, coord: Coord, direction: Vector)
     {
         self.visited_tiles.clear();
+        self.exit_tiles.clear();
         self.run_beam(&coord, &direction);
     }
 
+    // Beams still to follow are kept in a queue, splitters only add to it
     fn run_beam(&mut self, coord: &Coord, direction: &Vector)
     {
-        let mut direction = direction;
-        let mut coord = coord.clone();
+        let mut queue = vec![(*coord, *direction)];
 
-        loop
+        while let Some((coord, direction)) = queue.pop()
         {
+            // If we are on a path already visited (in same direction)
+            if !self.visited_tiles.insert((coord, direction)) { continue }
+
             let tile = &self.matrix[coord.x][coord.y];
 
-            debug!("Current coord: {:?} ({:?})", coord, *direction);
-            debug!("Current tile: {:?}", tile);
+            debug!("Current coord: {:?} ({:?})", coord, direction);
+            debug!("Current tile: {}", tile.symbol());
 
-            if self.visited_tiles.contains(&(coord, *direction))
+            for (exit_coord, next_direction) in tile.outputs(&coord, &direction)
             {
-                // If we are on a path already visited (in same direction)
-                debug!("Already visited ({:?}, {:?})", coord, direction);
-                return;
+                if exit_coord != coord
+                {
+                    self.exit_tiles.insert(exit_coord);
+                }
+
+                if let Some(next_coord) = self.next_coord(&exit_coord, &next_direction)
+                {
+                    queue.push((next_coord, next_direction));
+                }
             }
+        }
+    }
 
-            self.visited_tiles.insert((coord, *direction));
-
-            match tile
-            {
-                Tile::HorizontalSplitter => {
-                    if *direction == UP || *direction == DOWN
-                    {
-                        debug!("Found splitter, recursing...");
-
-                        let left_coord = self.next_coord(&coord, &LEFT);
-                        let right_coord = self.next_coord(&coord, &RIGHT);
-
-                        if let Some(left_coord) = left_coord
-                        {
-                            self.run_beam(&left_coord, &LEFT);
-                        }
-
-                        if let Some(right_coord) = right_coord
-                        {
-                            self.run_beam(&right_coord, &RIGHT);
-                        }
-
-                        break;
-                    }
-                },
-                Tile::VerticalSplitter => {
-                    if *direction == LEFT || *direction == RIGHT
-                    {
-                        debug!("Found splitter, recursing...");
+    pub fn energized_tiles_count(&self) -> usize
+    {
+        self.visited_tiles.iter()
+            .map(|(c, _)| c)
+            .chain(self.exit_tiles.iter())
+            .collect::<HashSet<_>>()
+            .len()
+    }
 
-                        let up_coord = self.next_coord(&coord, &UP);
-                        let down_coord = self.next_coord(&coord, &DOWN);
+    pub fn tile(&self, coord: &Coord) -> &dyn Component
+    {
+        self.matrix[coord.x][coord.y].as_ref()
+    }
 
-                        if let Some(up_coord) = up_coord
-                        {
-                            self.run_beam(&up_coord, &UP);
-                        }
+    // Number of distinct directions beams went through each counter in during the last run:
+    // beams going the same way through a tile follow the same path, so they are only counted once
+    pub fn counter_directions(&self) -> Vec<(Coord, usize)>
+    {
+        let mut counts = vec![];
 
-                        if let Some(down_coord) = down_coord
-                        {
-                            self.run_beam(&down_coord, &DOWN);
-                        }
+        for (x, row) in self.matrix.iter().enumerate()
+        {
+            for (y, _) in row.iter().enumerate().filter(|(_, t)| t.counts_beams())
+            {
+                let coord = Coord::new(x, y);
+                let count = [UP, DOWN, LEFT, RIGHT].iter()
+                    .filter(|d| self.visited_tiles.contains(&(coord, **d)))
+                    .count();
 
-                        break;
-                    }
-                },
-                Tile::MirrorSlash => {
-                    direction = match direction
-                    {
-                        &UP => &RIGHT,
-                        &DOWN => &LEFT,
-                        &LEFT => &DOWN,
-                        &RIGHT => &UP,
-                        _ => panic!("Unknown direction")
-                    };
-                },
-                Tile::MirrorAntiSlash => {
-                    direction = match direction
-                    {
-                        &UP => &LEFT,
-                        &DOWN => &RIGHT,
-                        &LEFT => &UP,
-                        &RIGHT => &DOWN,
-                        _ => panic!("Unknown direction")
-                    };
-                },
-                Tile::Empty => (),
+                counts.push((coord, count));
             }
+        }
 
-            let next_coord = self.next_coord(&coord, direction);
-
-            if next_coord.is_none()
-            {
-                debug!("Next tile is out-of-bound, returning...");
-                return
-            }
+        counts
+    }
 
-            coord = next_coord.unwrap();
-        }
+    pub fn tiles_count(&self) -> usize
+    {
+        self.matrix.len() * self.matrix[0].len()
     }
 
-    pub fn energized_tiles_count(&self) -> usize
+    // Position of the tile in row-major order
+    pub fn tile_index(&self, coord: &Coord) -> usize
     {
-        self.visited_tiles.iter()
-            .map(|(c, _)| c)
-            .collect::<HashSet<_>>()
-            .len()
+        coord.x * self.matrix[0].len() + coord.y
     }
 
-    fn next_coord(&self, coord: &Coord, direction: &Vector) -> Option<Coord>
+    pub fn next_coord(&self, coord: &Coord, direction: &Vector) -> Option<Coord>
     {
         let x = (coord.x as i32) + direction.x as i32;
         let y = (coord.y as i32) + direction.y as i32;
@@ -198,34 +162,38 @@ impl Facility
         Some(Coord { x: x as usize, y: y as usize })
     }
 
-    pub fn find_most_enegized_starting_point(&mut self) -> (Coord, Vector, usize)
+    // Every tile on the border with the direction pointing inside
+    pub fn edge_entries(&self) -> Vec<(Coord, Vector)>
     {
-        let mut results: Vec<(Coord, Vector, usize)> = vec![];
+        let mut entries = vec![];
+
         // Left / Right
         for i in 0..self.matrix.len()
         {
-            let coord = Coord::new(i, 0);
-            self.start_beam(coord, RIGHT);
-            results.push((coord, RIGHT, self.energized_tiles_count()));
-
-            let coord = Coord::new(i, self.matrix[0].len() - 1);
-            self.start_beam(coord, LEFT);
-            results.push((coord, LEFT, self.energized_tiles_count()));
+            entries.push((Coord::new(i, 0), RIGHT));
+            entries.push((Coord::new(i, self.matrix[0].len() - 1), LEFT));
         }
 
         // Top / Down
         for j in 0..self.matrix[0].len()
         {
-            let coord = Coord::new(0, j);
-            self.start_beam(coord, DOWN);
-            results.push((coord, DOWN, self.energized_tiles_count()));
-
-            let coord = Coord::new(self.matrix.len() - 1, j);
-            self.start_beam(coord, UP);
-            results.push((coord, UP, self.energized_tiles_count()));
+            entries.push((Coord::new(0, j), DOWN));
+            entries.push((Coord::new(self.matrix.len() - 1, j), UP));
         }
 
-        *results.iter()
+        entries
+    }
+
+    // Every entry shares the same beam graph instead of running a beam each time
+    pub fn find_most_enegized_starting_point(&self) -> (Coord, Vector, usize)
+    {
+        let entries = self.edge_entries();
+        let graph = BeamGraph::new(self, &entries);
+
+        debug!("{} segments in {} components", graph.segments_count(), graph.components_count());
+
+        entries.iter()
+            .map(|(coord, direction)| (*coord, *direction, graph.energized(coord, direction).unwrap()))
             .max_by(|a, b| a.2.cmp(&b.2))
             .unwrap()
     }
@@ -235,22 +203,35 @@ impl From<&str> for Facility
 {
     fn from(value: &str) -> Self
     {
-        let matrix = value.split('\n')
-            .map(|row| {
-                row.chars().map(|c| {
-                    match c
+        let mut portals: HashMap<char, Vec<Coord>> = HashMap::new();
+
+        let mut matrix: Vec<Vec<Box<dyn Component>>> = value.split('\n')
+            .enumerate()
+            .map(|(x, row)| {
+                row.chars().enumerate().map(|(y, c)| {
+                    // Any letter is one end of a portal, linked once every tile is known
+                    if c.is_ascii_uppercase()
                     {
-                        '|'  => Tile::VerticalSplitter,
-                        '-'  => Tile::HorizontalSplitter,
-                        '/'  => Tile::MirrorSlash,
-                        '\\' => Tile::MirrorAntiSlash,
-                        _    => Tile::Empty
+                        portals.entry(c).or_default().push(Coord::new(x, y));
+                        return Box::new(Empty) as Box<dyn Component>;
                     }
+
+                    component(c).unwrap_or_else(|| panic!("Unknown tile '{}' at {:?}", c, Coord::new(x, y)))
                 })
                 .collect()
             })
             .collect();
 
-        Facility { matrix, visited_tiles: HashSet::new() }
+        for (symbol, ends) in portals
+        {
+            if ends.len() != 2 { panic!("Portal '{}' needs exactly two ends, found {}", symbol, ends.len()) }
+
+            for (from, to) in [(ends[0], ends[1]), (ends[1], ends[0])]
+            {
+                matrix[from.x][from.y] = Box::new(Portal { symbol, target: to });
+            }
+        }
+
+        Facility { matrix, visited_tiles: HashSet::new(), exit_tiles: HashSet::new() }
     }
-}
\ No newline at end of file
+}
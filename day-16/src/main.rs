@@ -12,6 +12,8 @@ macro_rules! debug {
     }};
 }
 
+mod beam_graph;
+mod components;
 mod facility;
 
 fn main()
@@ -23,6 +25,11 @@ fn main()
 
     println!("Result: {}", facility.energized_tiles_count());
 
+    for (coord, directions) in facility.counter_directions()
+    {
+        debug!("Counter {:?}: {} direction(s)", coord, directions);
+    }
+
     let result2 = facility.find_most_enegized_starting_point();
     println!("Result 2: {}", result2.2);
 }
@@ -55,7 +62,7 @@ r#".|...\....
     #[test]
     fn test_example2()
     {
-        let mut f = Facility::from(
+        let f = Facility::from(
 r#".|...\....
 |.-.\.....
 .....|-...
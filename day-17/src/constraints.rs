@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use crate::factory::{Coord, Direction};
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Turn
+{
+    Left,
+    Right,
+    // Turning back is only allowed when asked for
+    Back
+}
+
+// Rules the crucible follows while moving
+#[derive(Clone, Debug)]
+pub struct Constraints
+{
+    // Blocks to move in a straight line before turning or stopping
+    pub min_straight: u32,
+    // Blocks after which it must turn
+    pub max_straight: u32,
+
+    pub allowed_turns: Vec<Turn>,
+    // Added to the heat loss of every turn
+    pub turn_penalty: u32,
+    // Added to the heat loss of a block when entering it
+    pub extra_costs: HashMap<Coord, u32>,
+
+    // Any of them ends the route, the bottom right block if empty
+    pub goals: Vec<Coord>
+}
+
+impl Constraints
+{
+    pub fn new(min_straight: u32, max_straight: u32) -> Self
+    {
+        Constraints {
+            min_straight,
+            max_straight,
+
+            allowed_turns: vec![Turn::Left, Turn::Right],
+            turn_penalty: 0,
+            extra_costs: HashMap::new(),
+
+            goals: vec![]
+        }
+    }
+
+    pub fn extra_cost(&self, coord: Coord) -> u32
+    {
+        *self.extra_costs.get(&coord).unwrap_or(&0)
+    }
+}
+
+impl Direction
+{
+    pub fn turned(&self, turn: Turn) -> Direction
+    {
+        match (self, turn)
+        {
+            (Direction::Up, Turn::Left) | (Direction::Down, Turn::Right) | (Direction::Right, Turn::Back) => Direction::Left,
+            (Direction::Up, Turn::Right) | (Direction::Down, Turn::Left) | (Direction::Left, Turn::Back) => Direction::Right,
+            (Direction::Left, Turn::Right) | (Direction::Right, Turn::Left) | (Direction::Down, Turn::Back) => Direction::Up,
+            (Direction::Left, Turn::Left) | (Direction::Right, Turn::Right) | (Direction::Up, Turn::Back) => Direction::Down
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::factory::{Direction, Factory, Node};
+    use super::{Constraints, Turn};
+
+    const EXAMPLE: &str = "2413432311323
+3215453535623
+3255245654254
+3446585845452
+4546657867536
+1438598798454
+4457876987766
+3637877979653
+4654967986887
+4564679986453
+1224686865563
+2546548887735
+4322674655533";
+
+    fn start() -> Node
+    {
+        Node::new((0, 0), None, 0)
+    }
+
+    #[test]
+    fn test_turned()
+    {
+        assert_eq!(Direction::Up.turned(Turn::Left), Direction::Left);
+        assert_eq!(Direction::Left.turned(Turn::Left), Direction::Down);
+        assert_eq!(Direction::Right.turned(Turn::Right), Direction::Down);
+        assert_eq!(Direction::Down.turned(Turn::Back), Direction::Up);
+    }
+
+    #[test]
+    fn test_same_as_crucible()
+    {
+        let factory = Factory::with_constraints(EXAMPLE, Constraints::new(0, 3));
+
+        assert_eq!(factory.find_route(start()).unwrap().heat_loss, 102);
+        assert_eq!(factory.find_lesser_heat_loss(start()), 102);
+    }
+
+    #[test]
+    fn test_turn_penalty()
+    {
+        let mut constraints = Constraints::new(0, u32::MAX);
+        constraints.turn_penalty = 100;
+
+        // Going around the 9 on the diagonal turns at least once
+        let factory = Factory::with_constraints("1111\n1991\n1991\n1111", constraints);
+        let route = factory.find_route(start()).unwrap();
+
+        assert_eq!(route.heat_loss, 106);
+        assert_eq!(factory.find_lesser_heat_loss(start()), 106);
+    }
+
+    #[test]
+    fn test_forbidden_turns()
+    {
+        // Only right turns: reaching the bottom right of a line means going straight
+        let mut constraints = Constraints::new(0, u32::MAX);
+        constraints.allowed_turns = vec![Turn::Right];
+
+        let factory = Factory::with_constraints("1111", constraints.clone());
+        assert_eq!(factory.find_route(start()).unwrap().moves.len(), 3);
+
+        // Right turns only, going down then left is impossible so we go right then down
+        let factory = Factory::with_constraints("19\n11", constraints);
+        let route = factory.find_route(Node::new((0, 0), Some(Direction::Right), 0)).unwrap();
+
+        assert_eq!(route.moves, vec![Direction::Right, Direction::Down]);
+        assert_eq!(route.heat_loss, 10);
+    }
+
+    #[test]
+    fn test_extra_costs_and_goals()
+    {
+        let mut constraints = Constraints::new(0, 3);
+        constraints.extra_costs.insert((0, 1), 50);
+        constraints.goals = vec![(0, 2), (2, 0)];
+
+        let factory = Factory::with_constraints("111\n111\n111", constraints);
+        let route = factory.find_route(start()).unwrap();
+
+        // (0, 2) is behind the expensive block, (2, 0) is the closest goal
+        assert_eq!(route.heat_loss, 2);
+        assert_eq!(route.coords.last(), Some(&(2, 0)));
+        assert_eq!(factory.find_lesser_heat_loss(start()), 2);
+    }
+
+    #[test]
+    fn test_huge_costs()
+    {
+        let mut constraints = Constraints::new(0, 3);
+        constraints.extra_costs.insert((0, 1), u32::MAX);
+        constraints.turn_penalty = u32::MAX / 2;
+
+        // Every route goes through a turn, the one through the expensive block saturates instead of overflowing
+        let factory = Factory::with_constraints("11\n11", constraints);
+
+        assert_eq!(factory.find_route(start()).unwrap().coords, vec![(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(factory.find_lesser_heat_loss(start()), 2 + u32::MAX / 2);
+    }
+
+    #[test]
+    fn test_unreachable()
+    {
+        let mut constraints = Constraints::new(0, 3);
+        constraints.allowed_turns = vec![];
+
+        // Can't turn and can't go straight for more than 3 blocks
+        let factory = Factory::with_constraints("11111", constraints);
+        assert!(factory.find_route(start()).is_none());
+    }
+}
@@ -2,6 +2,8 @@ use std::{collections::{HashMap, BinaryHeap, HashSet}, cmp::Reverse};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use crate::{constraints::Constraints, route::Route};
+
 pub type Coord = (usize, usize);
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, EnumIter, Debug)]
@@ -16,8 +18,9 @@ pub enum Direction
 pub struct Factory
 {
     matrix: Vec<Vec<u32>>,
-    min: u32,
-    max: u32
+    constraints: Constraints,
+    // Goals of the constraints, or the bottom right block
+    goals: HashSet<Coord>
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
@@ -37,7 +40,16 @@ impl Node
 
     pub fn neighboors(&self, size: (usize, usize), min: u32, max: u32) -> Vec<Node>
     {
-        let mut neighboors = vec![];
+        self.moves(size, &Constraints::new(min, max))
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect()
+    }
+
+    // Next nodes with the penalty for going there
+    pub fn moves(&self, size: (usize, usize), constraints: &Constraints) -> Vec<(Node, u32)>
+    {
+        let mut moves = vec![];
 
         // Initial node
         if self.direction.is_none()
@@ -47,48 +59,40 @@ impl Node
             {
                 if let Some(coord) = self.coord_for(d, size)
                 {
-                    neighboors.push(Node::new(coord, Some(d), self.straight_steps_count + 1));
+                    moves.push((Node::new(coord, Some(d), self.straight_steps_count + 1), 0));
                 }
             }
 
             // Return early
-            return neighboors;
+            return moves;
         }
 
+        let direction = self.direction.unwrap();
+
         // Can we continue straight ?
-        if self.straight_steps_count < max - 1
+        if self.straight_steps_count < constraints.max_straight.saturating_sub(1)
         {
-            if let Some(coord) = self.coord_for(self.direction.unwrap(), size)
+            if let Some(coord) = self.coord_for(direction, size)
             {
-                neighboors.push(Node::new(coord, self.direction, self.straight_steps_count + 1));
+                moves.push((Node::new(coord, self.direction, self.straight_steps_count + 1), 0));
             }
         }
 
         // +1 to avoid -1 min (it can be 0, and panic for u32)
-        if self.straight_steps_count + 1 >= min
+        if self.straight_steps_count + 1 >= constraints.min_straight
         {
-            // We turn perpendiculary (not forward nor backward)
-            for d in Direction::iter().filter(|d| *d != self.direction.unwrap() && *d != self.opposite_direction())
+            for turn in &constraints.allowed_turns
             {
+                let d = direction.turned(*turn);
+
                 if let Some(coord) = self.coord_for(d, size)
                 {
-                    neighboors.push(Node::new(coord, Some(d), 0));
+                    moves.push((Node::new(coord, Some(d), 0), constraints.turn_penalty));
                 }
             }
         }
 
-        neighboors
-    }
-
-    fn opposite_direction(&self) -> Direction
-    {
-        match self.direction.unwrap()
-        {
-            Direction::Up => Direction::Down,
-            Direction::Down => Direction::Up,
-            Direction::Left => Direction::Right,
-            Direction::Right => Direction::Left
-        }
+        moves
     }
 
     fn coord_for(&self, direction: Direction, max_size: (usize, usize)) -> Option<Coord>
@@ -123,7 +127,10 @@ impl From<&str> for Factory
             })
             .collect();
 
-        Factory { matrix, min: 0, max: 3 }
+        let mut factory = Factory { matrix, constraints: Constraints::new(0, 3), goals: HashSet::new() };
+        factory.goals = factory.collect_goals();
+
+        factory
     }
 }
 
@@ -131,10 +138,14 @@ impl Factory
 {
     pub fn new(min: u32, max: u32, content: &str) -> Self
     {
-        let mut factory = Factory::from(content);
+        Factory::with_constraints(content, Constraints::new(min, max))
+    }
 
-        factory.min = min;
-        factory.max = max;
+    pub fn with_constraints(content: &str, constraints: Constraints) -> Self
+    {
+        let mut factory = Factory::from(content);
+        factory.constraints = constraints;
+        factory.goals = factory.collect_goals();
 
         factory
     }
@@ -146,11 +157,80 @@ impl Factory
         path.last().unwrap().1
     }
 
+    // Cheapest route with A*, None if the end can't be reached
+    pub fn find_route(&self, start: Node) -> Option<Route>
+    {
+        let (path, heat_loss) = astar(self, start, &self.heat_loss_to_end())?;
+
+        Some(Route {
+            heat_loss,
+            coords: path.iter().map(|n| n.coord).collect(),
+            moves: path.iter().skip(1).map(|n| n.direction.unwrap()).collect()
+        })
+    }
+
     pub fn size(&self) -> (usize, usize)
     {
         (self.matrix.len(), self.matrix[0].len())
     }
 
+    pub fn heat_loss(&self, coord: Coord) -> u32
+    {
+        self.matrix[coord.0][coord.1]
+    }
+
+    // Heat loss plus the extra cost of the block
+    fn cost(&self, coord: Coord) -> u32
+    {
+        self.matrix[coord.0][coord.1].saturating_add(self.constraints.extra_cost(coord))
+    }
+
+    fn collect_goals(&self) -> HashSet<Coord>
+    {
+        if self.constraints.goals.is_empty() { HashSet::from([self.end_coord()]) }
+        else { self.constraints.goals.iter().copied().collect() }
+    }
+
+    // Ending on a goal needs enough straight blocks (+1 to avoid substract overflow, min can be 0)
+    fn can_stop_at(&self, node: &Node) -> bool
+    {
+        self.goals.contains(&node.coord) && node.straight_steps_count + 1 >= self.constraints.min_straight
+    }
+
+    // Heat loss from every block to the closest goal without any constraint on moves,
+    // never more than with them so it is an admissible heuristic (reverse Dijkstra from the goals)
+    fn heat_loss_to_end(&self) -> Vec<Vec<u32>>
+    {
+        let size = self.size();
+        let mut dist = vec![vec![u32::MAX; size.1]; size.0];
+        let mut queue: BinaryHeap<Reverse<(u32, Coord)>> = BinaryHeap::new();
+
+        for goal in &self.goals
+        {
+            dist[goal.0][goal.1] = 0;
+            queue.push(Reverse((0, *goal)));
+        }
+
+        while let Some(Reverse((d, coord))) = queue.pop()
+        {
+            if d > dist[coord.0][coord.1] { continue }
+
+            // Going from the neighboor to this block costs the heat loss of this block
+            for neighboor in Node::new(coord, None, 0).neighboors(size, 0, u32::MAX)
+            {
+                let alt = d.saturating_add(self.cost(coord));
+
+                if alt < dist[neighboor.coord.0][neighboor.coord.1]
+                {
+                    dist[neighboor.coord.0][neighboor.coord.1] = alt;
+                    queue.push(Reverse((alt, neighboor.coord)));
+                }
+            }
+        }
+
+        dist
+    }
+
     fn end_coord(&self) -> Coord
     {
         let (height, width) = self.size();
@@ -165,8 +245,6 @@ fn dijkstra(factory: &Factory, start: Node) -> Vec<(Node, u32)>
     let mut visited: HashSet<Node> = HashSet::new();
     let mut previous: HashMap<Node, Node> = HashMap::new();
 
-    let end_coord = factory.end_coord();
-
     dist.insert(start, 0);
     queue.push(Reverse((0, start)));
 
@@ -176,7 +254,7 @@ fn dijkstra(factory: &Factory, start: Node) -> Vec<(Node, u32)>
 
         visited.insert(node);
 
-        for neighboor in node.neighboors(factory.size(), factory.min, factory.max)
+        for (neighboor, penalty) in node.moves(factory.size(), &factory.constraints)
         {
             // Avoid loop and backtracking
             if visited.contains(&neighboor) { continue }
@@ -185,40 +263,76 @@ fn dijkstra(factory: &Factory, start: Node) -> Vec<(Node, u32)>
 
             let node_dist = *dist.get(&node).unwrap_or(&u32::MAX);
             let neighboor_dist = *dist.get(&neighboor).unwrap_or(&u32::MAX);
-            let alt_dist = node_dist + factory.matrix[neighboor.coord.0][neighboor.coord.1];
-
-            // TODO: if we are on the end node, we need to check that we are at the min steps
+            let alt_dist = node_dist.saturating_add(factory.cost(neighboor.coord)).saturating_add(penalty);
 
             if alt_dist < neighboor_dist
             {
                 debug!("PF: |--- Found shortest path: {} ({})", alt_dist, neighboor_dist);
 
-                // +1 to avoid substract overflow (min can be 0)
-                if neighboor.coord != end_coord || neighboor.straight_steps_count + 1 >= factory.min
-                {
-                    debug!("PF: |---- Possible path, saving...");
-
-                    if neighboor.coord == end_coord { debug!("PF: |----- Found path to the end"); }
-
-                    dist.insert(neighboor, alt_dist);
-                    queue.push(Reverse((alt_dist, neighboor)));
-                    previous.insert(neighboor, node);
-                }
+                dist.insert(neighboor, alt_dist);
+                queue.push(Reverse((alt_dist, neighboor)));
+                previous.insert(neighboor, node);
             }
         }
     }
 
-    let end_coord = factory.end_coord();
+    // Goals reached with too few straight blocks don't count
     let end_node = dist.iter()
-        .filter(|(node, _)| {
-            node.coord == end_coord
-        })
+        .filter(|(node, _)| factory.can_stop_at(node))
         .min_by(|a, b| a.1.cmp(b.1))
         .unwrap();
 
     unfold_dijkstra(*end_node.0, start, &previous, &dist)
 }
 
+// Same moves as dijkstra, nodes are taken by heat loss so far plus the heuristic, stopping at the first goal
+fn astar(factory: &Factory, start: Node, heuristic: &[Vec<u32>]) -> Option<(Vec<Node>, u32)>
+{
+    let mut dist: HashMap<Node, u32> = HashMap::new();
+    let mut queue: BinaryHeap<Reverse<(u32, u32, Node)>> = BinaryHeap::new();
+    let mut visited: HashSet<Node> = HashSet::new();
+    let mut previous: HashMap<Node, Node> = HashMap::new();
+
+    dist.insert(start, 0);
+    queue.push(Reverse((heuristic[start.coord.0][start.coord.1], 0, start)));
+
+    while let Some(Reverse((_, node_dist, node))) = queue.pop()
+    {
+        if !visited.insert(node) { continue }
+
+        // Heuristic is consistent, the first goal taken is the best one
+        if factory.can_stop_at(&node)
+        {
+            let mut path = vec![node];
+
+            while let Some(previous_node) = previous.get(path.last().unwrap())
+            {
+                path.push(*previous_node);
+            }
+
+            path.reverse();
+
+            return Some((path, node_dist));
+        }
+
+        for (neighboor, penalty) in node.moves(factory.size(), &factory.constraints)
+        {
+            if visited.contains(&neighboor) { continue }
+
+            let alt_dist = node_dist.saturating_add(factory.cost(neighboor.coord)).saturating_add(penalty);
+
+            if alt_dist < *dist.get(&neighboor).unwrap_or(&u32::MAX)
+            {
+                dist.insert(neighboor, alt_dist);
+                previous.insert(neighboor, node);
+                queue.push(Reverse((alt_dist.saturating_add(heuristic[neighboor.coord.0][neighboor.coord.1]), alt_dist, neighboor)));
+            }
+        }
+    }
+
+    None
+}
+
 fn unfold_dijkstra(end: Node, start: Node, previous: &HashMap<Node, Node>, dist: &HashMap<Node, u32>) -> Vec<(Node, u32)>
 {
     debug!("UNFOLD: -->");
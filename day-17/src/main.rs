@@ -1,8 +1,14 @@
 use std::time::Instant;
 
-use crate::factory::{Factory, Node};
+use crate::{constraints::{Constraints, Turn}, factory::{Factory, Node}};
 
 const DEBUG: bool = false;
+// Switch to false to use the plain Dijkstra search
+const ASTAR: bool = true;
+// Switch to true to print the route on the heat map
+const PRINT_ROUTE: bool = false;
+// Switch to true to also route an ultra crucible allowed to turn back
+const U_TURNS: bool = false;
 
 macro_rules! debug {
     ($($arg:tt)*) => {{
@@ -14,7 +20,9 @@ macro_rules! debug {
     }};
 }
 
+mod constraints;
 mod factory;
+mod route;
 
 fn main()
 {
@@ -24,18 +32,45 @@ fn main()
 
     let factory = Factory::from(content.as_str());
     println!("Result: {} ({:?})",
-        factory.find_lesser_heat_loss(Node::new((0, 0), None, 0)),
+        lesser_heat_loss(&factory),
         start.elapsed()
     );
 
     let factory = Factory::new(4, 10, content.as_str());
     println!("Result 2: {} ({:?})",
-        factory.find_lesser_heat_loss(Node::new((0, 0), None, 0)),
+        lesser_heat_loss(&factory),
         start.elapsed());
 
+    if U_TURNS
+    {
+        let mut constraints = Constraints::new(4, 10);
+        constraints.allowed_turns.push(Turn::Back);
+
+        let factory = Factory::with_constraints(content.as_str(), constraints);
+        println!("With U-turns: {} ({:?})",
+            lesser_heat_loss(&factory),
+            start.elapsed());
+    }
+
     println!("Total time: {:?}", start.elapsed());
 }
 
+fn lesser_heat_loss(factory: &Factory) -> u32
+{
+    let start = Node::new((0, 0), None, 0);
+
+    if !ASTAR { return factory.find_lesser_heat_loss(start) }
+
+    let route = factory.find_route(start).unwrap();
+
+    if PRINT_ROUTE
+    {
+        println!("{}\n{}", route.render(factory), route.moves_summary());
+    }
+
+    route.heat_loss
+}
+
 
 #[cfg(test)]
 mod tests
@@ -0,0 +1,139 @@
+use crate::factory::{Coord, Direction, Factory};
+
+// Path taken by the crucible, from the start to the end
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Route
+{
+    // Penalties and extra costs included
+    pub heat_loss: u32,
+    // Every block, start included
+    pub coords: Vec<Coord>,
+    // Move leading to each block but the start, so one less than coords
+    pub moves: Vec<Direction>
+}
+
+impl Direction
+{
+    pub fn arrow(&self) -> char
+    {
+        match self
+        {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>'
+        }
+    }
+
+    pub fn letter(&self) -> char
+    {
+        match self
+        {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R'
+        }
+    }
+}
+
+impl Route
+{
+    // Heat map with an arrow on every block entered, like in the puzzle statement
+    pub fn render(&self, factory: &Factory) -> String
+    {
+        let (height, width) = factory.size();
+
+        let mut lines: Vec<Vec<char>> = (0..height)
+            .map(|i| (0..width)
+                .map(|j| char::from_digit(factory.heat_loss((i, j)), 10).unwrap_or('?'))
+                .collect())
+            .collect();
+
+        for (coord, direction) in self.coords.iter().skip(1).zip(&self.moves)
+        {
+            lines[coord.0][coord.1] = direction.arrow();
+        }
+
+        lines.iter()
+            .map(|line| line.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Moves as letters, with how many blocks in a row: "R2 D1 ..."
+    pub fn moves_summary(&self) -> String
+    {
+        let mut summary: Vec<(Direction, usize)> = vec![];
+
+        for direction in &self.moves
+        {
+            match summary.last_mut()
+            {
+                Some((last, count)) if last == direction => *count += 1,
+                _ => summary.push((*direction, 1))
+            }
+        }
+
+        summary.iter()
+            .map(|(d, count)| format!("{}{}", d.letter(), count))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::factory::{Factory, Node, Direction};
+
+    const EXAMPLE: &str = "2413432311323
+3215453535623
+3255245654254
+3446585845452
+4546657867536
+1438598798454
+4457876987766
+3637877979653
+4654967986887
+4564679986453
+1224686865563
+2546548887735
+4322674655533";
+
+    #[test]
+    fn test_route()
+    {
+        let factory = Factory::from(EXAMPLE);
+        let route = factory.find_route(Node::new((0, 0), Some(Direction::Right), 0)).unwrap();
+
+        assert_eq!(route.heat_loss, 102);
+        assert_eq!(route.coords.first(), Some(&(0, 0)));
+        assert_eq!(route.coords.last(), Some(&(12, 12)));
+        assert_eq!(route.moves.len(), route.coords.len() - 1);
+
+        // Heat loss is the sum of every block entered
+        let sum: u32 = route.coords.iter().skip(1).map(|c| factory.heat_loss(*c)).sum();
+        assert_eq!(sum, 102);
+
+        // Never more than 3 blocks in the same direction
+        assert!(route.moves.windows(4).all(|w| w.iter().any(|d| *d != w[0])));
+
+        // Same as the Dijkstra search with the ultra crucible
+        let factory = Factory::new(4, 10, EXAMPLE);
+        let start = Node::new((0, 0), None, 0);
+
+        assert_eq!(factory.find_route(start).unwrap().heat_loss, factory.find_lesser_heat_loss(start));
+    }
+
+    #[test]
+    fn test_render()
+    {
+        let factory = Factory::from("119\n911");
+        let route = factory.find_route(Node::new((0, 0), None, 0)).unwrap();
+
+        assert_eq!(route.heat_loss, 3);
+        assert_eq!(route.render(&factory), "1>9\n9v>");
+        assert_eq!(route.moves_summary(), "R1 D1 R1");
+    }
+}
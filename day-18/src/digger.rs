@@ -1,5 +1,7 @@
 use core::panic;
 
+use geometry::Polygon;
+
 pub type Coord = (i64, i64);
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -91,6 +93,7 @@ impl Digger
         Digger { plan, points: vec![] }
     }
 
+    // Panics on plans not going back to the start or whose loop is not simple
     pub fn dig(&mut self)
     {
         self.points.clear();
@@ -105,23 +108,28 @@ impl Digger
             current_coord = move_in(current_coord, inst.direction, inst.length);
             self.points.push(current_coord);
         }
-    }
 
-    pub fn cubic_meters(&self) -> i64
-    {
-        let s1 = self.points.iter().zip(self.points.iter().skip(1))
-            .fold(0, |acc, (a, b)| acc + a.0 * b.1);
+        if current_coord != (0, 0)
+        {
+            panic!("Invalid dig plan: ends at {:?} instead of the start", current_coord);
+        }
 
-        let s2 = self.points.iter().zip(self.points.iter().skip(1))
-            .fold(0, |acc, (a, b)| acc + a.1 * b.0);
+        // Quadratic in the number of instructions, a few hundred of them is still quick
+        if let Some(defect) = self.polygon().defect()
+        {
+            panic!("Invalid dig plan: {:?}", defect);
+        }
+    }
 
-        let perimeter = self.points.iter().zip(self.points.iter().skip(1))
-            .fold(0, |acc, (a, b)| {
-                // "Rectangular" polygon so it work as either x or y coord will be diff than 0
-                acc + (a.0 - b.0 + (a.1 - b.1)).abs()
-            });
+    pub fn polygon(&self) -> Polygon
+    {
+        Polygon::new(self.points.clone())
+    }
 
-        ((s1 - s2) / 2).abs() + perimeter / 2 + 1
+    // Every dug cube is a lattice point of the edge or inside it
+    pub fn cubic_meters(&self) -> i64
+    {
+        self.polygon().lattice_points()
     }
 }
 
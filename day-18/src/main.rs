@@ -98,4 +98,35 @@ U 2 (#7a21e3)"
 
         assert_eq!(d.cubic_meters(), 952408144115);
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[should_panic]
+    fn test_not_closed()
+    {
+        Digger::from("R 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)").dig();
+    }
+
+    #[test]
+    #[should_panic(expected = "SelfIntersection")]
+    fn test_crossing()
+    {
+        // Goes right across its own first edge
+        let mut d = Digger::from("R 4 (#000000)
+D 2 (#000000)
+L 2 (#000000)
+U 4 (#000000)
+L 2 (#000000)
+D 2 (#000000)");
+
+        d.dig();
+    }
+
+    #[test]
+    #[should_panic(expected = "SelfIntersection")]
+    fn test_backtracking()
+    {
+        let mut d = Digger::from("R 4 (#000000)\nL 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)\nU 2 (#000000)");
+
+        d.dig();
+    }
+}
@@ -1,5 +1,6 @@
-use std::collections::{HashMap, VecDeque, HashSet};
+use std::{cell::Cell, collections::{HashMap, VecDeque, HashSet}, rc::Rc};
 
+use cycle::{Simulation, Strategy};
 use num::integer::lcm;
 
 use crate::modules::{FlipFlop, Conjunction, Broadcaster, Module, ModuleKind};
@@ -7,12 +8,14 @@ use crate::modules::{FlipFlop, Conjunction, Broadcaster, Module, ModuleKind};
 const BUTTON: &str = "button";
 const START: &str = "broadcaster";
 
+#[derive(Clone)]
 pub struct Factory
 {
     modules: HashMap<String, Box<dyn Module>>,
     bus: Bus
 }
 
+#[derive(Clone)]
 pub struct Bus
 {
     queue: VecDeque<Message>,
@@ -91,7 +94,8 @@ impl Factory
         }
     }
 
-    pub fn run_until_low_rx(&mut self) -> usize
+    // Must be called on a factory as it was parsed, the button presses are counted from there
+    pub fn run_until_low_rx(&self) -> usize
     {
         // Based on GraphViz analysis
         // let nand_before_rx = "ll";
@@ -109,46 +113,43 @@ impl Factory
         // -> Input at LOW (flipped NAND)
         // -> Counters inputs at HIGH
 
-        // We need to isolate each cycle by removing output for NAND gates
+        self.first_common_fire(&counters)
+    }
 
-        for (_, output) in counters
-        {
-            self.modules.get_mut(output).unwrap().clear_outputs();
-        }
+    // First button press where every counter NAND fires, with (branch input, NAND) for each counter
+    pub fn first_common_fire(&self, counters: &[(&str, &str)]) -> usize
+    {
+        // Each counter is a separate branch only reached from its own input, we run them one at a time
+        // The NAND of a counter resets it when it fires, so the branch must be back to its initial state right after:
+        // then it fires again every `length` presses and the first press where all of them fire is the LCM
 
         let mut iterations = HashMap::new();
 
-        // Now we run each branch to find the lower iteration were output is low
         for (input, output) in counters
         {
             verbose!("Finding cycle for {}", output);
 
-            let mut n = 0;
-            self.bus.reset();
-
-            loop
-            {
-                n += 1;
+            let mut branch = Branch {
+                factory: self.clone(),
+                input: input.to_string(),
+                nand: output.to_string(),
+                presses: 0,
+                first_fire: Rc::new(Cell::new(None))
+            };
+            branch.factory.bus.reset();
 
-                if n % 1000 == 0
-                {
-                    verbose!("[{}] {}", output, n);
-                }
+            let cycle = cycle::detect(&branch, Strategy::HashMap);
+            let fire = branch.first_fire.get();
 
-                self.bus.send(Message::new(MessageKind::LowPulse, BUTTON.to_string(), &vec![input.to_string()]));
-                self.run_once();
+            verbose!("[{}] Found cycle at {} (starting at {}), firing at {:?}", output, cycle.length, cycle.start, fire);
 
-                let output_module = self.modules[output].as_any().downcast_ref::<Conjunction>().unwrap();
-
-                // Check if state for output is OK
-                if output_module.state_all_high()
-                {
-                    verbose!("[{}] Found cycle at {}", output, n);
-                    break;
-                }
+            // Fires once per cycle, on the press bringing the counter back to its initial state
+            if cycle.start != 0 || fire != Some(cycle.length)
+            {
+                panic!("Counter {} does not reset when it fires (cycle {:?}, firing at {:?})", output, cycle, fire);
             }
 
-            iterations.insert(output.to_string(), n);
+            iterations.insert(output.to_string(), cycle.length);
         }
 
         let min_cycle = iterations.values().fold(1, |acc, c| lcm(acc, *c));
@@ -156,11 +157,16 @@ impl Factory
         return min_cycle;
     }
 
-    fn run_once(&mut self)
+    // Returns the modules which sent a low pulse during the press
+    fn run_once(&mut self) -> HashSet<String>
     {
+        let mut low_senders = HashSet::new();
+
         debug!("--- Button pressed ---");
         while let Some(message) = self.bus.pop()
         {
+            if message.kind == MessageKind::LowPulse { low_senders.insert(message.src.clone()); }
+
             for dest in &message.dest
             {
                 debug!("{}({:?}) [{:?}] -> {}({:?})",
@@ -180,6 +186,8 @@ impl Factory
         }
 
         debug!("--- Iteration done ---\n ");
+
+        low_senders
     }
 
     pub fn low_pulses(&self) -> usize
@@ -192,6 +200,18 @@ impl Factory
         self.bus.message_kind_count(MessageKind::HighPulse)
     }
 
+    // State of every flip-flop, modules are never added after parsing so clones iterate in the same order
+    // Conjunctions remember the last pulse of each input, which is the state of the flip-flop it comes from,
+    // or what the conjunction it comes from sent for the state of its own inputs, so they are left out:
+    // with them, the parsed state (nothing sent yet) would never come back
+    fn fingerprint(&self) -> Vec<bool>
+    {
+        self.modules.values()
+            .filter(|m| m.kind() == ModuleKind::FlipFlop)
+            .flat_map(|m| m.state())
+            .collect()
+    }
+
     fn update_conjunction_modules_inputs(&mut self)
     {
         let mut inputs: HashMap<String, HashSet<String>> = HashMap::new();
@@ -218,6 +238,43 @@ impl Factory
     }
 }
 
+// Factory where the button only feeds a single input
+#[derive(Clone)]
+struct Branch
+{
+    factory: Factory,
+    input: String,
+
+    // NAND of the counter, and the first press where it sent a low pulse
+    // Shared between clones so it is recorded by whichever one cycle detection runs
+    nand: String,
+    presses: usize,
+    first_fire: Rc<Cell<Option<usize>>>
+}
+
+impl Simulation for Branch
+{
+    type Fingerprint = Vec<bool>;
+
+    fn step(&mut self)
+    {
+        self.factory.bus.send(Message::new(MessageKind::LowPulse, BUTTON.to_string(), &vec![self.input.clone()]));
+        let low_senders = self.factory.run_once();
+
+        self.presses += 1;
+
+        if self.first_fire.get().is_none() && low_senders.contains(&self.nand)
+        {
+            self.first_fire.set(Some(self.presses));
+        }
+    }
+
+    fn fingerprint(&self) -> Self::Fingerprint
+    {
+        self.factory.fingerprint()
+    }
+}
+
 impl Bus
 {
     pub fn new() -> Self
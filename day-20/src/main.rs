@@ -45,7 +45,8 @@ fn main()
         start.elapsed()
     );
 
-    let result2 = f.run_until_low_rx();
+    // Counters must start from their initial state, not from where part 1 left them
+    let result2 = Factory::from(content.as_str()).run_until_low_rx();
     println!("Result 2: {} ({:?})",
         result2,
         start.elapsed());
@@ -126,4 +127,27 @@ mod tests
         assert_eq!(f.low_pulses(), 4250);
         assert_eq!(f.high_pulses(), 2750);
     }
+
+    #[test]
+    fn test_counters_p2()
+    {
+        // Counter a-b fires at 3 (11), counter c-d-e at 5 (101), their NAND resets them
+        let f = Factory::from(
+"broadcaster -> a, c
+%a -> b, na
+%b -> na
+&na -> a, ia
+&ia -> ll
+%c -> d, nc
+%d -> e
+%e -> nc
+&nc -> c, d, ic
+&ic -> ll
+&ll -> rx"
+);
+
+        assert_eq!(f.first_common_fire(&[("a", "na")]), 3);
+        assert_eq!(f.first_common_fire(&[("c", "nc")]), 5);
+        assert_eq!(f.first_common_fire(&[("a", "na"), ("c", "nc")]), 15);
+    }
 }
\ No newline at end of file
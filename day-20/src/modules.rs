@@ -19,14 +19,26 @@ pub trait Module
     fn kind(&self) -> ModuleKind;
 
     fn outputs(&self) -> &Vec<String>;
-    fn clear_outputs(&mut self);
 
     fn run(&mut self, message: &Message, bus: &mut Bus);
 
+    // Memory of the module, as bits
+    fn state(&self) -> Vec<bool>;
+    fn clone_box(&self) -> Box<dyn Module>;
+
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+impl Clone for Box<dyn Module>
+{
+    fn clone(&self) -> Self
+    {
+        self.clone_box()
+    }
+}
+
+#[derive(Clone)]
 pub struct FlipFlop
 {
     name: String,
@@ -34,6 +46,7 @@ pub struct FlipFlop
     state: bool,
 }
 
+#[derive(Clone)]
 pub struct Conjunction
 {
     name: String,
@@ -52,13 +65,9 @@ impl Conjunction
             self.state.insert(input.to_string(), MessageKind::LowPulse);
         }
     }
-
-    pub fn state_all_high(&self) -> bool
-    {
-        self.state.values().all(|m| *m == MessageKind::HighPulse)
-    }
 }
 
+#[derive(Clone)]
 pub struct Broadcaster
 {
     name: String,
@@ -82,6 +91,16 @@ impl Module for FlipFlop
         ModuleKind::FlipFlop
     }
 
+    fn state(&self) -> Vec<bool>
+    {
+        vec![self.state]
+    }
+
+    fn clone_box(&self) -> Box<dyn Module>
+    {
+        Box::new(self.clone())
+    }
+
     fn outputs(&self) -> &Vec<String>
     {
         &self.outputs
@@ -111,13 +130,7 @@ impl Module for FlipFlop
     fn as_any_mut(&mut self) -> &mut dyn Any
     {
         self
-    }
-
-    fn clear_outputs(&mut self)
-    {
-        self.outputs.clear();
-    }
-}
+    }}
 
 impl Module for Conjunction
 {
@@ -136,6 +149,19 @@ impl Module for Conjunction
         ModuleKind::Conjunction
     }
 
+    fn state(&self) -> Vec<bool>
+    {
+        let mut inputs: Vec<_> = self.state.iter().collect();
+        inputs.sort_by_key(|(name, _)| *name);
+
+        inputs.iter().map(|(_, kind)| **kind == MessageKind::HighPulse).collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Module>
+    {
+        Box::new(self.clone())
+    }
+
     fn outputs(&self) -> &Vec<String>
     {
         &self.outputs
@@ -160,13 +186,7 @@ impl Module for Conjunction
     fn as_any_mut(&mut self) -> &mut dyn Any
     {
         self
-    }
-
-    fn clear_outputs(&mut self)
-    {
-        self.outputs.clear();
-    }
-}
+    }}
 
 impl Module for Broadcaster
 {
@@ -185,6 +205,16 @@ impl Module for Broadcaster
         ModuleKind::Broadcaster
     }
 
+    fn state(&self) -> Vec<bool>
+    {
+        vec![]
+    }
+
+    fn clone_box(&self) -> Box<dyn Module>
+    {
+        Box::new(self.clone())
+    }
+
     fn outputs(&self) -> &Vec<String>
     {
         &self.outputs
@@ -203,10 +233,4 @@ impl Module for Broadcaster
     fn as_any_mut(&mut self) -> &mut dyn Any
     {
         self
-    }
-
-    fn clear_outputs(&mut self)
-    {
-        self.outputs.clear();
-    }
-}
\ No newline at end of file
+    }}
\ No newline at end of file
@@ -0,0 +1,318 @@
+// Integer point, (x, y) with y going up: swap the meaning if your coords are (row, column)
+pub type Point = (i64, i64);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Winding
+{
+    Clockwise,
+    CounterClockwise
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Location
+{
+    Inside,
+    Outside,
+    Boundary
+}
+
+// Why a polygon can't be used for area computations
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Defect
+{
+    TooFewVertices,
+    // Edge going nowhere, index of the edge
+    EmptyEdge(usize),
+    // Indexes of two edges touching or crossing each other (overlapping for adjacent edges)
+    SelfIntersection(usize, usize)
+}
+
+// Closed polygon, edge `i` goes from vertex `i` to vertex `i + 1` and the last one back to the first
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Polygon
+{
+    pub vertices: Vec<Point>
+}
+
+impl Polygon
+{
+    // The first vertex may be repeated at the end, it is dropped
+    pub fn new(mut vertices: Vec<Point>) -> Self
+    {
+        if vertices.len() > 1 && vertices.first() == vertices.last()
+        {
+            vertices.pop();
+        }
+
+        Polygon { vertices }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_
+    {
+        let n = self.vertices.len();
+
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    // Shoelace formula, doubled to stay in integers: positive when counterclockwise
+    pub fn double_signed_area(&self) -> i64
+    {
+        self.edges()
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum()
+    }
+
+    pub fn double_area(&self) -> i64
+    {
+        self.double_signed_area().abs()
+    }
+
+    pub fn winding(&self) -> Winding
+    {
+        if self.double_signed_area() < 0 { Winding::Clockwise }
+        else { Winding::CounterClockwise }
+    }
+
+    // Sum of the edge lengths, only for edges along an axis (panics on diagonal ones)
+    pub fn manhattan_perimeter(&self) -> i64
+    {
+        self.edges()
+            .map(|(a, b)| {
+                if a.0 != b.0 && a.1 != b.1 { panic!("Diagonal edge from {:?} to {:?}", a, b) }
+
+                (b.0 - a.0).abs() + (b.1 - a.1).abs()
+            })
+            .sum()
+    }
+
+    // Lattice points on the edges, vertices included, edges in any direction
+    pub fn boundary_points(&self) -> i64
+    {
+        self.edges()
+            .map(|(a, b)| gcd((b.0 - a.0).abs(), (b.1 - a.1).abs()))
+            .sum()
+    }
+
+    // Pick's theorem: A = I + B / 2 - 1, only for simple polygons
+    pub fn interior_points(&self) -> i64
+    {
+        (self.double_area() - self.boundary_points() + 2) / 2
+    }
+
+    // Interior and boundary points
+    pub fn lattice_points(&self) -> i64
+    {
+        self.interior_points() + self.boundary_points()
+    }
+
+    // How many times the polygon goes around `point` counterclockwise, 0 when outside
+    // (undefined for points on the boundary)
+    pub fn winding_number(&self, point: Point) -> i64
+    {
+        let mut winding = 0;
+
+        for (a, b) in self.edges()
+        {
+            // Half open on y so a ray going through a vertex counts once
+            if a.1 <= point.1 && b.1 > point.1 && cross(a, b, point) > 0
+            {
+                winding += 1;
+            }
+            else if a.1 > point.1 && b.1 <= point.1 && cross(a, b, point) < 0
+            {
+                winding -= 1;
+            }
+        }
+
+        winding
+    }
+
+    pub fn locate(&self, point: Point) -> Location
+    {
+        if self.edges().any(|(a, b)| on_segment(point, a, b)) { return Location::Boundary }
+
+        if self.winding_number(point) != 0 { Location::Inside }
+        else { Location::Outside }
+    }
+
+    // First pair of edges touching each other other than by their shared vertex
+    pub fn self_intersection(&self) -> Option<(usize, usize)>
+    {
+        let edges: Vec<(Point, Point)> = self.edges().collect();
+        let n = edges.len();
+
+        for i in 0..n
+        {
+            for j in i + 1..n
+            {
+                let (a, b) = edges[i];
+                let (c, d) = edges[j];
+
+                let adjacent = j == i + 1 || (i == 0 && j == n - 1);
+
+                if adjacent
+                {
+                    // Only the shared vertex is common, unless one goes back along the other
+                    let (shared, p, q) = if j == i + 1 { (b, a, d) } else { (a, b, c) };
+
+                    let backwards = cross(shared, p, q) == 0 &&
+                        (p.0 - shared.0) * (q.0 - shared.0) + (p.1 - shared.1) * (q.1 - shared.1) > 0;
+
+                    if backwards { return Some((i, j)) }
+                }
+                else if segments_intersect(a, b, c, d)
+                {
+                    return Some((i, j));
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn is_simple(&self) -> bool
+    {
+        self.defect().is_none()
+    }
+
+    pub fn defect(&self) -> Option<Defect>
+    {
+        if self.vertices.len() < 3 { return Some(Defect::TooFewVertices) }
+
+        if let Some(i) = self.edges().position(|(a, b)| a == b)
+        {
+            return Some(Defect::EmptyEdge(i));
+        }
+
+        self.self_intersection().map(|(i, j)| Defect::SelfIntersection(i, j))
+    }
+}
+
+pub fn gcd(a: i64, b: i64) -> i64
+{
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Positive when `p` is on the left of `a` -> `b`, 0 when aligned
+fn cross(a: Point, b: Point, p: Point) -> i64
+{
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+fn on_segment(p: Point, a: Point, b: Point) -> bool
+{
+    cross(a, b, p) == 0 &&
+        a.0.min(b.0) <= p.0 && p.0 <= a.0.max(b.0) &&
+        a.1.min(b.1) <= p.1 && p.1 <= a.1.max(b.1)
+}
+
+// Touching counts
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool
+{
+    let d1 = cross(c, d, a).signum();
+    let d2 = cross(c, d, b).signum();
+    let d3 = cross(a, b, c).signum();
+    let d4 = cross(a, b, d).signum();
+
+    if d1 * d2 < 0 && d3 * d4 < 0 { return true }
+
+    on_segment(a, c, d) || on_segment(b, c, d) || on_segment(c, a, b) || on_segment(d, a, b)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Defect, Location, Polygon, Winding};
+
+    fn square() -> Polygon
+    {
+        Polygon::new(vec![(0, 0), (4, 0), (4, 4), (0, 4), (0, 0)])
+    }
+
+    #[test]
+    fn test_area()
+    {
+        let square = square();
+
+        assert_eq!(square.vertices.len(), 4);
+        assert_eq!(square.double_signed_area(), 32);
+        assert_eq!(square.winding(), Winding::CounterClockwise);
+
+        let reversed = Polygon::new(square.vertices.iter().rev().copied().collect());
+
+        assert_eq!(reversed.double_signed_area(), -32);
+        assert_eq!(reversed.double_area(), 32);
+        assert_eq!(reversed.winding(), Winding::Clockwise);
+    }
+
+    #[test]
+    fn test_lattice_points()
+    {
+        let square = square();
+
+        assert_eq!(square.manhattan_perimeter(), 16);
+        assert_eq!(square.boundary_points(), 16);
+        assert_eq!(square.interior_points(), 9);
+        assert_eq!(square.lattice_points(), 25);
+
+        // Diagonal edge from (4, 0) to (0, 2) also goes through (2, 1)
+        let triangle = Polygon::new(vec![(0, 0), (4, 0), (0, 2)]);
+
+        assert_eq!(triangle.boundary_points(), 4 + 2 + 2);
+        assert_eq!(triangle.interior_points(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Diagonal edge")]
+    fn test_perimeter_diagonal()
+    {
+        Polygon::new(vec![(0, 0), (4, 0), (0, 2)]).manhattan_perimeter();
+    }
+
+    #[test]
+    fn test_locate()
+    {
+        // U shape, open on top
+        let u = Polygon::new(vec![(0, 0), (6, 0), (6, 6), (4, 6), (4, 2), (2, 2), (2, 6), (0, 6)]);
+
+        assert_eq!(u.locate((1, 1)), Location::Inside);
+        assert_eq!(u.locate((5, 5)), Location::Inside);
+        assert_eq!(u.locate((3, 4)), Location::Outside);
+        assert_eq!(u.locate((7, 2)), Location::Outside);
+        // Ray going through vertices
+        assert_eq!(u.locate((-1, 2)), Location::Outside);
+        assert_eq!(u.locate((1, 2)), Location::Inside);
+        assert_eq!(u.locate((3, 2)), Location::Boundary);
+        assert_eq!(u.locate((6, 6)), Location::Boundary);
+
+        assert_eq!(u.winding_number((1, 1)), 1);
+        assert_eq!(Polygon::new(u.vertices.iter().rev().copied().collect()).winding_number((1, 1)), -1);
+    }
+
+    #[test]
+    fn test_defects()
+    {
+        assert_eq!(square().defect(), None);
+        assert!(square().is_simple());
+
+        assert_eq!(Polygon::new(vec![(0, 0), (1, 0)]).defect(), Some(Defect::TooFewVertices));
+        assert_eq!(Polygon::new(vec![(0, 0), (1, 0), (1, 0), (0, 1)]).defect(), Some(Defect::EmptyEdge(1)));
+
+        // Bow tie
+        let bow_tie = Polygon::new(vec![(0, 0), (2, 2), (2, 0), (0, 2)]);
+        assert_eq!(bow_tie.defect(), Some(Defect::SelfIntersection(0, 2)));
+
+        // Going back along the previous edge
+        let spike = Polygon::new(vec![(0, 0), (4, 0), (4, 4), (4, 2), (0, 4)]);
+        assert_eq!(spike.defect(), Some(Defect::SelfIntersection(1, 2)));
+
+        // Touching itself on a vertex
+        let eight = Polygon::new(vec![(0, 0), (2, 0), (2, 2), (4, 2), (4, 4), (2, 4), (2, 2), (0, 2)]);
+        assert!(!eight.is_simple());
+
+        // Aligned consecutive edges are fine
+        let aligned = Polygon::new(vec![(0, 0), (2, 0), (4, 0), (4, 4), (0, 4)]);
+        assert!(aligned.is_simple());
+        assert_eq!(aligned.interior_points(), 9);
+    }
+}